@@ -0,0 +1,286 @@
+// api.rs - JSON endpoints under minimalist://api/ for internal pages and user scripts
+use serde::{Serialize, Deserialize};
+use wry::http::{Method, StatusCode};
+use crate::browser_core::{BrowserCore, MemoryStats, TabData};
+use crate::process_memory::{ProcessKind, ProcessMemory};
+use crate::router::{RouteRequest, RouteResponse, Router};
+use crate::tab_history::now_secs;
+
+/// What the new tab and memory pages show, in one request.
+#[derive(Serialize)]
+struct BrowserStatus {
+    memory: MemoryStats,
+    tab_count: usize,
+    booster_active: bool,
+    ultimate_boost_active: bool,
+    flash_enabled: bool,
+    flash_status: String,
+}
+
+#[derive(Deserialize)]
+struct MemoryUpdate {
+    booster: Option<bool>,
+    ultimate_boost: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct TabList {
+    tabs: Vec<TabData>,
+    active_tab_id: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum TabAction {
+    Create { url: Option<String> },
+    Close { tab_id: usize },
+    Switch { tab_id: usize },
+    /// Navigates the active tab when `tab_id` is omitted.
+    Navigate { tab_id: Option<usize>, url: String },
+}
+
+/// Only the simple preferences; search engines and redirects have their own pages.
+#[derive(Deserialize)]
+struct SettingsUpdate {
+    restore_session: Option<bool>,
+    index_page_content: Option<bool>,
+    clean_urls: Option<bool>,
+    https_only: Option<bool>,
+    discard_after_mins: Option<u64>,
+    auto_ultimate_boost: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct FlashInfo {
+    enabled: bool,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct FlashUpdate {
+    enabled: bool,
+}
+
+/// Everything minimalist://tasks lists.
+#[derive(Serialize)]
+struct TaskList {
+    tabs: Vec<TabTask>,
+    processes: Vec<ProcessMemory>,
+}
+
+#[derive(Serialize)]
+struct TabTask {
+    id: usize,
+    title: String,
+    display_url: String,
+    active: bool,
+    discarded: bool,
+    /// Seconds since the tab's current page was opened.
+    age_secs: u64,
+    /// An estimate: tabs share the web content processes, so their memory is
+    /// split evenly between the tabs that are loaded. Discarded tabs hold none.
+    memory_kb: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct ApiError {
+    error: String,
+}
+
+pub fn register(router: &mut Router) {
+    router
+        .route("/api/status", status)
+        .route("/api/memory", memory)
+        .route("/api/memory/history", memory_history)
+        .route("/api/tabs", tabs)
+        .route("/api/settings", settings)
+        .route("/api/flash", flash)
+        .route("/api/tasks", tasks);
+}
+
+fn status(request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse {
+    if !is_read(request) {
+        return RouteResponse::method_not_allowed(&[Method::GET, Method::HEAD]);
+    }
+    let memory = core.get_memory_stats();
+    RouteResponse::json(&BrowserStatus {
+        tab_count: memory.tab_count,
+        booster_active: memory.booster_active,
+        ultimate_boost_active: memory.ultimate_boost_active,
+        flash_enabled: core.is_flash_enabled(),
+        flash_status: core.get_flash_status(),
+        memory,
+    })
+}
+
+fn memory(request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse {
+    if request.method == Method::POST {
+        let update: MemoryUpdate = match request.json() {
+            Ok(update) => update,
+            Err(e) => return failure(StatusCode::BAD_REQUEST, e),
+        };
+        if let Some(enabled) = update.booster {
+            core.set_booster_enabled(enabled);
+        }
+        if let Some(enabled) = update.ultimate_boost {
+            core.set_ultimate_boost_enabled(enabled);
+        }
+    } else if !is_read(request) {
+        return read_write_only();
+    }
+    RouteResponse::json(&core.get_memory_stats())
+}
+
+/// JSON by default; `?format=csv` for the samples as a spreadsheet.
+fn memory_history(request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse {
+    if !is_read(request) {
+        return RouteResponse::method_not_allowed(&[Method::GET, Method::HEAD]);
+    }
+    let history = core.get_memory_history();
+    match request.param("format") {
+        Some("csv") => RouteResponse::csv(history.to_csv(), "memory-history.csv"),
+        None | Some("json") => RouteResponse::json(history),
+        Some(other) => failure(StatusCode::BAD_REQUEST, format!("Unknown format {:?}", other)),
+    }
+}
+
+fn tabs(request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse {
+    if request.method == Method::POST {
+        let action: TabAction = match request.json() {
+            Ok(action) => action,
+            Err(e) => return failure(StatusCode::BAD_REQUEST, e),
+        };
+        if let Err(e) = apply_tab_action(core, action) {
+            return failure(StatusCode::NOT_FOUND, e);
+        }
+    } else if !is_read(request) {
+        return read_write_only();
+    }
+    RouteResponse::json(&TabList {
+        tabs: core.get_tabs().into_iter().cloned().collect(),
+        active_tab_id: core.get_active_tab().map(|t| t.id),
+    })
+}
+
+fn apply_tab_action(core: &mut BrowserCore, action: TabAction) -> Result<(), String> {
+    match action {
+        TabAction::Create { url } => {
+            core.create_tab(url.as_deref().unwrap_or(""));
+        }
+        TabAction::Close { tab_id } => {
+            if !core.get_tabs().iter().any(|t| t.id == tab_id) {
+                return Err(format!("No tab with id {}", tab_id));
+            }
+            core.close_tab(tab_id);
+            if core.get_tabs().is_empty() {
+                core.create_tab("");
+            }
+        }
+        TabAction::Switch { tab_id } => {
+            if !core.switch_tab(tab_id) {
+                return Err(format!("No tab with id {}", tab_id));
+            }
+        }
+        TabAction::Navigate { tab_id, url } => {
+            let target = tab_id.or_else(|| core.get_active_tab().map(|t| t.id));
+            if target.and_then(|id| core.navigate(id, &url)).is_none() {
+                return Err("No tab to navigate".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn settings(request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse {
+    if request.method == Method::POST {
+        let update: SettingsUpdate = match request.json() {
+            Ok(update) => update,
+            Err(e) => return failure(StatusCode::BAD_REQUEST, e),
+        };
+        if let Some(enabled) = update.restore_session {
+            core.set_restore_session(enabled);
+        }
+        if let Some(enabled) = update.index_page_content {
+            core.set_index_page_content(enabled);
+        }
+        if let Some(enabled) = update.clean_urls {
+            core.set_clean_urls(enabled);
+        }
+        if let Some(enabled) = update.https_only {
+            core.set_https_only(enabled);
+        }
+        if let Some(minutes) = update.discard_after_mins {
+            core.set_discard_after_mins(minutes);
+        }
+        if let Some(enabled) = update.auto_ultimate_boost {
+            core.set_auto_ultimate_boost(enabled);
+        }
+    } else if !is_read(request) {
+        return read_write_only();
+    }
+    RouteResponse::json(core.get_settings())
+}
+
+fn flash(request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse {
+    if request.method == Method::POST {
+        match request.json::<FlashUpdate>() {
+            Ok(update) => core.set_flash_enabled(update.enabled),
+            Err(e) => return failure(StatusCode::BAD_REQUEST, e),
+        }
+    } else if !is_read(request) {
+        return read_write_only();
+    }
+    RouteResponse::json(&FlashInfo {
+        enabled: core.is_flash_enabled(),
+        status: core.get_flash_status(),
+    })
+}
+
+fn tasks(request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse {
+    if !is_read(request) {
+        return RouteResponse::method_not_allowed(&[Method::GET, Method::HEAD]);
+    }
+    let processes = core.get_memory_stats().breakdown.map(|b| b.processes).unwrap_or_default();
+    let web_kb = processes
+        .iter()
+        .filter(|p| p.kind == ProcessKind::Web)
+        .map(|p| p.usage.charged_kb())
+        .sum::<u64>();
+    let active_tab_id = core.get_active_tab().map(|t| t.id);
+    let loaded = core.get_tabs().iter().filter(|t| !t.discarded).count().max(1) as u64;
+    let now = now_secs();
+    let tabs = core
+        .get_tabs()
+        .into_iter()
+        .map(|tab| {
+            let active = Some(tab.id) == active_tab_id;
+            let memory_kb = match (tab.discarded, processes.is_empty()) {
+                (true, _) => Some(0),
+                (false, true) => None,
+                (false, false) => Some(web_kb / loaded),
+            };
+            TabTask {
+                id: tab.id,
+                title: tab.title.clone(),
+                display_url: tab.display_url.clone(),
+                active,
+                discarded: tab.discarded,
+                age_secs: tab.history.current().map_or(0, |e| now.saturating_sub(e.timestamp)),
+                memory_kb,
+            }
+        })
+        .collect();
+    RouteResponse::json(&TaskList { tabs, processes })
+}
+
+fn is_read(request: &RouteRequest) -> bool {
+    matches!(request.method, Method::GET | Method::HEAD)
+}
+
+fn read_write_only() -> RouteResponse {
+    RouteResponse::method_not_allowed(&[Method::GET, Method::HEAD, Method::POST])
+}
+
+fn failure(status: StatusCode, error: String) -> RouteResponse {
+    RouteResponse::json(&ApiError { error }).with_status(status)
+}
@@ -24,12 +24,60 @@ lazy_static! {
             content_type: "text/html; charset=utf-8",
         });
         
+        pages.insert("tasks", BrowserPage {
+            title: "Task Manager",
+            content: TASKS_HTML,
+            content_type: "text/html; charset=utf-8",
+        });
+        
         pages.insert("flash", BrowserPage {
             title: "Flash Test",
             content: FLASH_TEST_HTML,
             content_type: "text/html; charset=utf-8",
         });
         
+        pages.insert("history", BrowserPage {
+            title: "History",
+            content: HISTORY_HTML,
+            content_type: "text/html; charset=utf-8",
+        });
+        
+        pages.insert("search", BrowserPage {
+            title: "Search Pages",
+            content: SEARCH_HTML,
+            content_type: "text/html; charset=utf-8",
+        });
+        
+        pages.insert("redirects", BrowserPage {
+            title: "Redirects",
+            content: REDIRECTS_HTML,
+            content_type: "text/html; charset=utf-8",
+        });
+        
+        pages.insert("bookmarks", BrowserPage {
+            title: "Bookmarks",
+            content: BOOKMARKS_HTML,
+            content_type: "text/html; charset=utf-8",
+        });
+        
+        pages.insert("restore", BrowserPage {
+            title: "Restore Session",
+            content: RESTORE_HTML,
+            content_type: "text/html; charset=utf-8",
+        });
+        
+        pages.insert("bridge.js", BrowserPage {
+            title: "Page Bridge",
+            content: PAGE_BRIDGE_JS,
+            content_type: "application/javascript; charset=utf-8",
+        });
+        
+        pages.insert("https-only", BrowserPage {
+            title: "HTTPS Not Available",
+            content: HTTPS_ONLY_HTML,
+            content_type: "text/html; charset=utf-8",
+        });
+        
         pages.insert("error", BrowserPage {
             title: "Error",
             content: ERROR_HTML,
@@ -46,6 +94,31 @@ pub struct BrowserPage {
     pub content_type: &'static str,
 }
 
+// Loaded by internal pages; relays BrowserCore commands through the shell
+const PAGE_BRIDGE_JS: &str = r#"window.minimalist = (function() {
+  let nextId = 1;
+  const pending = new Map();
+
+  window.addEventListener('message', (e) => {
+    if (e.source !== window.parent || !e.data || e.data.minimalist !== 'reply') return;
+    const waiter = pending.get(e.data.reqId);
+    if (!waiter) return;
+    pending.delete(e.data.reqId);
+    e.data.error ? waiter.reject(new Error(e.data.error)) : waiter.resolve(e.data.reply);
+  });
+
+  return {
+    invoke(cmd, args = {}) {
+      return new Promise((resolve, reject) => {
+        const reqId = nextId++;
+        pending.set(reqId, { resolve, reject });
+        window.parent.postMessage({ minimalist: 'invoke', reqId, cmd, args }, '*');
+      });
+    }
+  };
+})();
+"#;
+
 // Minimal New Tab Page
 const NEW_TAB_HTML: &str = r#"<!doctype html>
 <html lang="en">
@@ -83,19 +156,29 @@ const NEW_TAB_HTML: &str = r#"<!doctype html>
     <h1>Minimalist Browser</h1>
     <div class="stats">
       <div class="stat-card">
-        <div class="stat-value" id="memUsage">--</div>
+        <div class="stat-value" id="memUsage">{{memory_mb}} MB</div>
         <div class="stat-label">Memory Usage</div>
       </div>
       <div class="stat-card">
-        <div class="stat-value" id="tabCount">1</div>
+        <div class="stat-value" id="tabCount">{{tab_count}}</div>
         <div class="stat-label">Open Tabs</div>
       </div>
       <div class="stat-card">
-        <div class="stat-value" id="boosterStatus">OFF</div>
+        <div class="stat-value" id="boosterStatus">{{booster}}</div>
         <div class="stat-label">Booster Mode</div>
       </div>
     </div>
   </div>
+  <script>
+    function refresh() {
+      fetch('api/status').then(r => r.json()).then(s => {
+        document.getElementById('memUsage').textContent = s.memory.total_mb + ' MB';
+        document.getElementById('tabCount').textContent = s.tab_count;
+        document.getElementById('boosterStatus').textContent = s.booster_active ? 'ON' : 'OFF';
+      }).catch(() => {});
+    }
+    setInterval(refresh, 2000);
+  </script>
 </body>
 </html>"#;
 
@@ -129,7 +212,28 @@ const SETTINGS_HTML: &str = r#"<!doctype html>
       border-bottom: 1px solid rgba(255, 255, 255, 0.05);
     }
     .setting:last-child { border: none; }
+    input[type="text"], select {
+      background: rgba(11, 18, 32, 0.95);
+      border: 1px solid rgba(79, 209, 197, 0.2);
+      color: #e6eef3;
+      padding: 6px 8px;
+      border-radius: 6px;
+    }
+    button {
+      background: rgba(79, 209, 197, 0.15);
+      color: #4fd1c5;
+      border: none;
+      padding: 6px 12px;
+      border-radius: 6px;
+      cursor: pointer;
+      font-weight: 600;
+    }
+    .engine-url { font-size: 12px; color: #9aa6b2; word-break: break-all; }
+    .engine-form { display: flex; gap: 8px; flex-wrap: wrap; padding-top: 12px; }
+    .engine-form input { flex: 1; min-width: 120px; }
+    .error { color: #ff6b6b; font-size: 13px; min-height: 1em; }
   </style>
+  <script src="bridge.js"></script>
 </head>
 <body>
   <div class="container">
@@ -138,10 +242,304 @@ const SETTINGS_HTML: &str = r#"<!doctype html>
       <h2>Performance</h2>
       <div class="setting">
         <span>Booster Mode</span>
-        <input type="checkbox">
+        <input type="checkbox" id="booster" {{booster_checked}}>
+      </div>
+      <div class="setting">
+        <span>Ultimate Boost (clears site data)</span>
+        <input type="checkbox" id="ultimateBoost" {{ultimate_boost_checked}}>
+      </div>
+      <div class="setting">
+        <span>Play Flash content with Ruffle</span>
+        <input type="checkbox" id="flash" {{flash_checked}}>
+      </div>
+      <div class="setting">
+        <span>Discard background tabs after</span>
+        <select id="discardAfter">
+          <option value="0">Never</option>
+          <option value="10">10 minutes</option>
+          <option value="30">30 minutes</option>
+          <option value="60">1 hour</option>
+        </select>
+      </div>
+      <div class="setting">
+        <span>Allow Ultimate Boost when over the memory limit (clears site data)</span>
+        <input type="checkbox" id="autoUltimateBoost">
+      </div>
+    </div>
+    <div class="section">
+      <h2>Startup</h2>
+      <div class="setting">
+        <span>Reopen tabs from last session</span>
+        <input type="checkbox" id="restoreSession">
+      </div>
+    </div>
+    <div class="section">
+      <h2>Search</h2>
+      <div class="setting">
+        <span>Default search engine</span>
+        <select id="defaultEngine"></select>
+      </div>
+      <div id="engines"></div>
+      <div class="engine-form">
+        <input type="text" id="engineName" placeholder="Name">
+        <input type="text" id="engineKeyword" placeholder="Keyword">
+        <input type="text" id="engineUrl" placeholder="URL with %s in place of the query">
+        <button id="addEngine">Add</button>
+      </div>
+      <div class="error" id="engineError"></div>
+      <div id="offeredSection" hidden>
+        <h2>Offered by sites you visited</h2>
+        <div id="offered"></div>
+      </div>
+    </div>
+    <div class="section">
+      <h2>Bang Shortcuts</h2>
+      <p class="engine-url">Type <code>!gh tokio</code> or <code>tokio !gh</code> in the address bar to go straight to a site's search.</p>
+      <div id="bangs"></div>
+      <div class="engine-form">
+        <input type="text" id="bangTrigger" placeholder="Trigger (e.g. jira)">
+        <input type="text" id="bangName" placeholder="Name">
+        <input type="text" id="bangUrl" placeholder="URL with %s in place of the query">
+        <button id="addBang">Save</button>
+      </div>
+      <div class="error" id="bangError"></div>
+    </div>
+    <div class="section">
+      <h2>Privacy</h2>
+      <div class="setting">
+        <span>Index the text of visited pages for <a href="minimalist:///search" style="color: #4fd1c5;">search</a></span>
+        <input type="checkbox" id="indexPages">
+      </div>
+      <div class="setting">
+        <span>Remove tracking parameters (utm_source, fbclid, ...) from addresses</span>
+        <input type="checkbox" id="cleanUrls">
+      </div>
+      <div class="setting">
+        <span>HTTPS-Only mode: load sites securely, and ask before falling back to HTTP</span>
+        <input type="checkbox" id="httpsOnly">
+      </div>
+      <div id="httpExceptionsSection" hidden>
+        <p class="engine-url">Always loaded over HTTP:</p>
+        <div id="httpExceptions"></div>
+      </div>
+      <div class="setting">
+        <span>Rewrite addresses with your own rules</span>
+        <a href="minimalist:///redirects" style="color: #4fd1c5;">Redirects</a>
       </div>
     </div>
   </div>
+  <script>
+    const booster = document.getElementById('booster');
+    const ultimateBoost = document.getElementById('ultimateBoost');
+    const flash = document.getElementById('flash');
+    const restoreSession = document.getElementById('restoreSession');
+    const indexPages = document.getElementById('indexPages');
+    const cleanUrls = document.getElementById('cleanUrls');
+    const httpsOnly = document.getElementById('httpsOnly');
+    const discardAfter = document.getElementById('discardAfter');
+    const autoUltimateBoost = document.getElementById('autoUltimateBoost');
+
+    function renderHttpExceptions(hosts) {
+      $('httpExceptionsSection').hidden = hosts.length === 0;
+      const list = $('httpExceptions');
+      list.innerHTML = '';
+      hosts.forEach(host => {
+        const row = document.createElement('div');
+        row.className = 'setting';
+        const label = document.createElement('span');
+        label.textContent = host;
+        const remove = document.createElement('button');
+        remove.textContent = 'Remove';
+        remove.addEventListener('click', () => {
+          minimalist.invoke('remove_http_exception', { host })
+            .then(s => renderHttpExceptions(s.http_exceptions));
+        });
+        row.append(label, remove);
+        list.appendChild(row);
+      });
+    }
+    const $ = (id) => document.getElementById(id);
+
+    function renderEngines(registry) {
+      const select = $('defaultEngine');
+      select.innerHTML = '';
+      const list = $('engines');
+      list.innerHTML = '';
+      registry.engines.forEach(engine => {
+        const option = document.createElement('option');
+        option.value = engine.keyword;
+        option.textContent = engine.name;
+        option.selected = engine.keyword === registry.default_keyword;
+        select.appendChild(option);
+
+        const row = document.createElement('div');
+        row.className = 'setting';
+        const info = document.createElement('div');
+        const name = document.createElement('div');
+        name.textContent = engine.name + ' (' + engine.keyword + ')';
+        const url = document.createElement('div');
+        url.className = 'engine-url';
+        url.textContent = engine.url_template;
+        info.append(name, url);
+        const remove = document.createElement('button');
+        remove.textContent = 'Remove';
+        remove.addEventListener('click', () => editEngines('remove_search_engine', { keyword: engine.keyword }));
+        row.append(info, remove);
+        list.appendChild(row);
+      });
+    }
+
+    function editEngines(cmd, args) {
+      $('engineError').textContent = '';
+      return minimalist.invoke(cmd, args).then(
+        s => { renderEngines(s.search_engines); return true; },
+        err => { $('engineError').textContent = err.message; return false; }
+      );
+    }
+
+    function renderOffered(engines) {
+      $('offeredSection').hidden = engines.length === 0;
+      const list = $('offered');
+      list.innerHTML = '';
+      engines.forEach(engine => {
+        const row = document.createElement('div');
+        row.className = 'setting';
+        const info = document.createElement('div');
+        const name = document.createElement('div');
+        name.textContent = engine.name + (engine.description ? ' — ' + engine.description : '');
+        const url = document.createElement('div');
+        url.className = 'engine-url';
+        url.textContent = engine.url_template;
+        info.append(name, url);
+        const keyword = document.createElement('input');
+        keyword.type = 'text';
+        keyword.value = engine.suggested_keyword;
+        keyword.size = 8;
+        keyword.title = 'Keyword';
+        const add = document.createElement('button');
+        add.textContent = 'Add';
+        add.addEventListener('click', () => {
+          editEngines('install_search_engine', { descriptor_url: engine.descriptor_url, keyword: keyword.value })
+            .then(loadOffered);
+        });
+        const dismiss = document.createElement('button');
+        dismiss.textContent = 'Dismiss';
+        dismiss.addEventListener('click', () => {
+          minimalist.invoke('dismiss_search_engine', { descriptor_url: engine.descriptor_url }).then(loadOffered);
+        });
+        const actions = document.createElement('div');
+        actions.append(keyword, ' ', add, ' ', dismiss);
+        row.append(info, actions);
+        list.appendChild(row);
+      });
+    }
+
+    function loadOffered() {
+      minimalist.invoke('get_discovered_search_engines').then(r => renderOffered(r.engines));
+    }
+
+    $('defaultEngine').addEventListener('change', () => {
+      editEngines('set_default_search_engine', { keyword: $('defaultEngine').value });
+    });
+    $('addEngine').addEventListener('click', () => {
+      editEngines('add_search_engine', {
+        name: $('engineName').value,
+        keyword: $('engineKeyword').value,
+        url_template: $('engineUrl').value
+      }).then(added => {
+        if (added) $('engineName').value = $('engineKeyword').value = $('engineUrl').value = '';
+      });
+    });
+
+    function renderBangs(bangs) {
+      const list = $('bangs');
+      list.innerHTML = '';
+      bangs.forEach(bang => {
+        const row = document.createElement('div');
+        row.className = 'setting';
+        const info = document.createElement('div');
+        const name = document.createElement('div');
+        name.textContent = '!' + bang.trigger + ' — ' + bang.name + (bang.builtin ? '' : ' (custom)');
+        const url = document.createElement('div');
+        url.className = 'engine-url';
+        url.textContent = bang.url_template;
+        info.append(name, url);
+        const edit = document.createElement('button');
+        edit.textContent = 'Edit';
+        edit.addEventListener('click', () => {
+          $('bangTrigger').value = bang.trigger;
+          $('bangName').value = bang.name;
+          $('bangUrl').value = bang.url_template;
+        });
+        const remove = document.createElement('button');
+        remove.textContent = 'Remove';
+        remove.addEventListener('click', () => editBangs('remove_bang', { trigger: bang.trigger }));
+        const actions = document.createElement('div');
+        actions.append(edit, ' ', remove);
+        row.append(info, actions);
+        list.appendChild(row);
+      });
+    }
+
+    function editBangs(cmd, args) {
+      $('bangError').textContent = '';
+      return minimalist.invoke(cmd, args).then(
+        r => { renderBangs(r.bangs); return true; },
+        err => { $('bangError').textContent = err.message; return false; }
+      );
+    }
+
+    $('addBang').addEventListener('click', () => {
+      editBangs('set_bang', {
+        trigger: $('bangTrigger').value,
+        name: $('bangName').value,
+        url_template: $('bangUrl').value
+      }).then(saved => {
+        if (saved) $('bangTrigger').value = $('bangName').value = $('bangUrl').value = '';
+      });
+    });
+
+    minimalist.invoke('get_bangs').then(r => renderBangs(r.bangs));
+
+    minimalist.invoke('get_settings').then(s => {
+      restoreSession.checked = s.restore_session;
+      indexPages.checked = s.index_page_content;
+      cleanUrls.checked = s.clean_urls;
+      httpsOnly.checked = s.https_only;
+      discardAfter.value = String(s.discard_after_mins);
+      autoUltimateBoost.checked = s.auto_ultimate_boost;
+      renderHttpExceptions(s.http_exceptions);
+      renderEngines(s.search_engines);
+    });
+    loadOffered();
+    booster.addEventListener('change', () => {
+      minimalist.invoke('set_booster', { enabled: booster.checked });
+    });
+    ultimateBoost.addEventListener('change', () => {
+      minimalist.invoke('set_ultimate_boost', { enabled: ultimateBoost.checked });
+    });
+    flash.addEventListener('change', () => {
+      minimalist.invoke('set_flash_enabled', { enabled: flash.checked });
+    });
+    restoreSession.addEventListener('change', () => {
+      minimalist.invoke('set_restore_session', { enabled: restoreSession.checked });
+    });
+    indexPages.addEventListener('change', () => {
+      minimalist.invoke('set_index_page_content', { enabled: indexPages.checked });
+    });
+    cleanUrls.addEventListener('change', () => {
+      minimalist.invoke('set_clean_urls', { enabled: cleanUrls.checked });
+    });
+    httpsOnly.addEventListener('change', () => {
+      minimalist.invoke('set_https_only', { enabled: httpsOnly.checked });
+    });
+    discardAfter.addEventListener('change', () => {
+      minimalist.invoke('set_discard_after', { minutes: Number(discardAfter.value) });
+    });
+    autoUltimateBoost.addEventListener('change', () => {
+      minimalist.invoke('set_auto_ultimate_boost', { enabled: autoUltimateBoost.checked });
+    });
+  </script>
 </body>
 </html>"#;
 
@@ -172,6 +570,19 @@ const MEMORY_STATS_HTML: &str = r#"<!doctype html>
     }
     .stat-title { font-size: 13px; color: #9aa6b2; margin-bottom: 12px; }
     .stat-value { font-size: 32px; font-weight: 700; color: #4fd1c5; }
+    .stat-detail { font-size: 13px; color: #9aa6b2; margin-top: 8px; }
+    .graph-card { margin-top: 16px; }
+    .graph-header { display: flex; justify-content: space-between; align-items: center; }
+    canvas { width: 100%; height: 240px; display: block; }
+    button {
+      background: rgba(79, 209, 197, 0.15);
+      color: #4fd1c5;
+      border: none;
+      padding: 6px 12px;
+      border-radius: 6px;
+      cursor: pointer;
+      font-weight: 600;
+    }
   </style>
 </head>
 <body>
@@ -180,14 +591,313 @@ const MEMORY_STATS_HTML: &str = r#"<!doctype html>
     <div class="stats-grid">
       <div class="stat-card">
         <div class="stat-title">Total Memory</div>
-        <div class="stat-value">-- MB</div>
+        <div class="stat-value" id="totalMemory">{{memory_mb}} MB</div>
+        <div class="stat-detail" id="memoryLimit">{{memory_percent}}% of {{memory_limit_mb}} MB limit</div>
+      </div>
+      <div class="stat-card">
+        <div class="stat-title">Processes</div>
+        <div class="stat-detail">UI <span id="uiMemory">{{ui_mb}}</span> MB</div>
+        <div class="stat-detail">Web content <span id="webMemory">{{web_mb}}</span> MB</div>
+        <div class="stat-detail">Network <span id="networkMemory">{{network_mb}}</span> MB</div>
+      </div>
+      <div class="stat-card">
+        <div class="stat-title">Measurements</div>
+        <div class="stat-detail">RSS <span id="rssMemory">{{rss_mb}}</span> MB</div>
+        <div class="stat-detail">PSS <span id="pssMemory">{{pss_mb}}</span> MB</div>
+        <div class="stat-detail">USS <span id="ussMemory">{{uss_mb}}</span> MB</div>
+        <div class="stat-detail">Swap <span id="swapMemory">{{swap_mb}}</span> MB</div>
+        <div class="stat-detail">Anonymous <span id="anonMemory">{{anon_mb}}</span> MB · File-backed <span id="fileMemory">{{file_mb}}</span> MB</div>
       </div>
       <div class="stat-card">
         <div class="stat-title">Tab Count</div>
-        <div class="stat-value">--</div>
+        <div class="stat-value" id="tabCount">{{tab_count}}</div>
+      </div>
+      <div class="stat-card">
+        <div class="stat-title">Booster Mode</div>
+        <div class="stat-value" id="booster">{{booster}}</div>
+      </div>
+      <div class="stat-card">
+        <div class="stat-title">Ultimate Boost</div>
+        <div class="stat-value" id="ultimateBoost">{{ultimate_boost}}</div>
+      </div>
+      <div class="stat-card">
+        <div class="stat-title">Flash</div>
+        <div class="stat-detail" id="flashStatus">{{flash_status}}</div>
+      </div>
+    </div>
+    <div class="stat-card graph-card">
+      <div class="graph-header">
+        <div class="stat-title">Last hour</div>
+        <div>
+          <button id="exportCsv">Export CSV</button>
+          <button id="exportJson">Export JSON</button>
+        </div>
       </div>
+      <canvas id="graph"></canvas>
+      <div class="stat-detail" id="graphEmpty">Samples are taken every 5 seconds.</div>
     </div>
   </div>
+  <script>
+    const onOff = on => on ? 'ON' : 'OFF';
+    function refresh() {
+      fetch('api/status').then(r => r.json()).then(s => {
+        const m = s.memory;
+        const percent = Math.floor(m.total_mb * 100 / Math.max(m.limit_mb, 1));
+        document.getElementById('totalMemory').textContent = m.total_mb + ' MB';
+        document.getElementById('memoryLimit').textContent = percent + '% of ' + m.limit_mb + ' MB limit';
+        const b = m.breakdown;
+        document.getElementById('uiMemory').textContent = b ? b.ui_mb : '--';
+        document.getElementById('webMemory').textContent = b ? b.web_mb : '--';
+        document.getElementById('networkMemory').textContent = b ? b.network_mb : '--';
+        const mb = kb => kb == null ? '--' : Math.floor(kb / 1024);
+        const t = b ? b.totals : {};
+        document.getElementById('rssMemory').textContent = mb(t.rss_kb);
+        document.getElementById('pssMemory').textContent = mb(t.pss_kb);
+        document.getElementById('ussMemory').textContent = mb(t.uss_kb);
+        document.getElementById('swapMemory').textContent = mb(t.swap_kb);
+        document.getElementById('anonMemory').textContent = mb(t.anon_kb);
+        document.getElementById('fileMemory').textContent = mb(t.file_kb);
+        document.getElementById('tabCount').textContent = s.tab_count;
+        document.getElementById('booster').textContent = onOff(s.booster_active);
+        document.getElementById('ultimateBoost').textContent = onOff(s.ultimate_boost_active);
+        document.getElementById('flashStatus').textContent = s.flash_enabled ? s.flash_status : 'Disabled';
+      }).catch(() => {});
+    }
+    setInterval(refresh, 2000);
+
+    // Total memory against the limit, with mode changes as vertical markers.
+    function drawGraph(history) {
+      const canvas = document.getElementById('graph');
+      const samples = history.samples;
+      document.getElementById('graphEmpty').hidden = samples.length > 1;
+      const ratio = window.devicePixelRatio || 1;
+      canvas.width = canvas.clientWidth * ratio;
+      canvas.height = canvas.clientHeight * ratio;
+      const ctx = canvas.getContext('2d');
+      ctx.scale(ratio, ratio);
+      const w = canvas.clientWidth, h = canvas.clientHeight, pad = 24;
+      ctx.clearRect(0, 0, w, h);
+      if (samples.length < 2) return;
+
+      const start = samples[0].timestamp;
+      const span = Math.max(samples[samples.length - 1].timestamp - start, 1);
+      const top = Math.max(...samples.map(s => Math.max(s.total_mb, s.limit_mb))) * 1.1;
+      const x = t => pad + (t - start) / span * (w - 2 * pad);
+      const y = mb => h - pad - mb / top * (h - 2 * pad);
+
+      ctx.font = '11px system-ui';
+      ctx.strokeStyle = 'rgba(255, 107, 107, 0.6)';
+      ctx.setLineDash([4, 4]);
+      ctx.beginPath();
+      ctx.moveTo(pad, y(samples[samples.length - 1].limit_mb));
+      ctx.lineTo(w - pad, y(samples[samples.length - 1].limit_mb));
+      ctx.stroke();
+      ctx.setLineDash([]);
+
+      ctx.fillStyle = '#9aa6b2';
+      ctx.strokeStyle = 'rgba(154, 166, 178, 0.4)';
+      history.annotations.forEach(a => {
+        ctx.beginPath();
+        ctx.moveTo(x(a.timestamp), pad);
+        ctx.lineTo(x(a.timestamp), h - pad);
+        ctx.stroke();
+        ctx.fillText(a.label, x(a.timestamp) + 4, pad + 10);
+      });
+
+      ctx.strokeStyle = '#4fd1c5';
+      ctx.lineWidth = 2;
+      ctx.beginPath();
+      samples.forEach((s, i) => i ? ctx.lineTo(x(s.timestamp), y(s.total_mb)) : ctx.moveTo(x(s.timestamp), y(s.total_mb)));
+      ctx.stroke();
+      ctx.lineWidth = 1;
+      ctx.fillText(Math.round(top) + ' MB', 2, pad - 8);
+    }
+
+    function refreshGraph() {
+      fetch('api/memory/history').then(r => r.json()).then(drawGraph).catch(() => {});
+    }
+    refreshGraph();
+    setInterval(refreshGraph, 5000);
+
+    function download(format, type) {
+      fetch('api/memory/history?format=' + format).then(r => r.text()).then(text => {
+        const link = document.createElement('a');
+        link.href = URL.createObjectURL(new Blob([text], { type }));
+        link.download = 'memory-history.' + format;
+        link.click();
+        URL.revokeObjectURL(link.href);
+      });
+    }
+    document.getElementById('exportCsv').addEventListener('click', () => download('csv', 'text/csv'));
+    document.getElementById('exportJson').addEventListener('click', () => download('json', 'application/json'));
+  </script>
+</body>
+</html>"#;
+
+const TASKS_HTML: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Task Manager</title>
+  <style>
+    body {
+      margin: 0;
+      padding: 40px;
+      background: linear-gradient(135deg, #0f1720 0%, #071017 100%);
+      color: #e6eef3;
+      font-family: system-ui;
+    }
+    .container { max-width: 1000px; margin: 0 auto; }
+    h1 { font-size: 28px; margin-bottom: 24px; }
+    h2 { font-size: 14px; color: #4fd1c5; margin: 24px 0 8px; }
+    .toolbar { display: flex; justify-content: flex-end; margin-bottom: 8px; }
+    table {
+      width: 100%;
+      border-collapse: collapse;
+      background: rgba(11, 18, 32, 0.8);
+      border-radius: 12px;
+      overflow: hidden;
+    }
+    th, td { padding: 8px 12px; text-align: left; font-size: 13px; }
+    th { color: #9aa6b2; font-weight: 600; }
+    tr + tr td { border-top: 1px solid rgba(255, 255, 255, 0.05); }
+    td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; }
+    .url { font-size: 12px; color: #9aa6b2; max-width: 360px; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+    .badge { font-size: 11px; color: #4fd1c5; margin-left: 6px; }
+    .actions { white-space: nowrap; }
+    button {
+      background: rgba(79, 209, 197, 0.15);
+      color: #4fd1c5;
+      border: none;
+      padding: 4px 10px;
+      border-radius: 6px;
+      cursor: pointer;
+      font-weight: 600;
+    }
+    button:disabled { opacity: 0.4; cursor: default; }
+    .error { color: #ff6b6b; font-size: 13px; min-height: 1em; }
+    .note { font-size: 12px; color: #9aa6b2; margin: 0 0 8px; }
+  </style>
+  <script src="bridge.js"></script>
+</head>
+<body>
+  <div class="container">
+    <h1>Task Manager</h1>
+    <div class="toolbar"><button id="gcBtn" title="Drop WebKit's memory cache">Clear cache</button></div>
+    <div class="error" id="error"></div>
+    <h2>Tabs</h2>
+    <p class="note">Tabs share the web content processes, so CPU time is only known per process.</p>
+    <table>
+      <thead><tr><th>Tab</th><th class="num" title="Web process memory split between loaded tabs">Memory (est.)</th><th class="num">Open for</th><th></th></tr></thead>
+      <tbody id="tabs"></tbody>
+    </table>
+    <h2>Processes</h2>
+    <table>
+      <thead><tr><th>Process</th><th class="num">PID</th><th class="num">PSS</th><th class="num">USS</th><th class="num">CPU time</th><th class="num">Age</th></tr></thead>
+      <tbody id="processes"></tbody>
+    </table>
+  </div>
+  <script>
+    const $ = id => document.getElementById(id);
+    const KINDS = { ui: 'Browser', web: 'Web content', network: 'Network', other: 'Other' };
+
+    function mb(kb) {
+      return kb == null ? '--' : (kb / 1024).toFixed(1) + ' MB';
+    }
+
+    function duration(secs) {
+      if (secs == null) return '--';
+      if (secs < 60) return secs + 's';
+      if (secs < 3600) return Math.floor(secs / 60) + 'm';
+      return Math.floor(secs / 3600) + 'h ' + Math.floor(secs % 3600 / 60) + 'm';
+    }
+
+    function cell(text, className) {
+      const td = document.createElement('td');
+      td.textContent = text;
+      if (className) td.className = className;
+      return td;
+    }
+
+    function badge(text) {
+      const span = document.createElement('span');
+      span.className = 'badge';
+      span.textContent = text;
+      return span;
+    }
+
+    function action(label, title, handler, disabled) {
+      const button = document.createElement('button');
+      button.textContent = label;
+      button.title = title;
+      button.disabled = !!disabled;
+      button.addEventListener('click', () => {
+        $('error').textContent = '';
+        handler().then(refresh).catch(e => { $('error').textContent = e.message; });
+      });
+      return button;
+    }
+
+    function renderTabs(tabs) {
+      $('tabs').replaceChildren(...tabs.map(tab => {
+        const info = cell('');
+        const title = document.createElement('div');
+        title.textContent = tab.title || tab.display_url;
+        if (tab.active) title.append(badge('active'));
+        if (tab.discarded) title.append(badge('discarded'));
+        const url = document.createElement('div');
+        url.className = 'url';
+        url.textContent = tab.display_url;
+        info.append(title, url);
+
+        const actions = cell('', 'actions');
+        actions.append(
+          action('Reload', 'Reload this tab',
+            () => minimalist.invoke('reload_tab', { tab_id: tab.id }), tab.discarded),
+          ' ',
+          action('Discard', 'Unload until the tab is next shown',
+            () => minimalist.invoke('discard_tab', { tab_id: tab.id }), tab.active || tab.discarded),
+          ' ',
+          action('Close', 'Close this tab', () => minimalist.invoke('close_tab', { tab_id: tab.id })),
+        );
+
+        // Tabs share the web processes, so this is their total split evenly.
+        const memory = cell(tab.discarded ? 'Not loaded' : tab.memory_kb == null ? '--' : '~' + mb(tab.memory_kb), 'num');
+        if (!tab.discarded) memory.title = 'Estimate: web process memory split evenly between loaded tabs';
+        const row = document.createElement('tr');
+        row.append(info, memory, cell(duration(tab.age_secs), 'num'), actions);
+        return row;
+      }));
+    }
+
+    function renderProcesses(processes) {
+      $('processes').replaceChildren(...processes.map(p => {
+        const row = document.createElement('tr');
+        row.append(
+          cell(KINDS[p.kind] + ' (' + p.name + ')'),
+          cell(p.pid, 'num'),
+          cell(mb(p.pss_kb != null ? p.pss_kb : p.rss_kb), 'num'),
+          cell(mb(p.uss_kb), 'num'),
+          cell(p.cpu_time_ms == null ? '--' : (p.cpu_time_ms / 1000).toFixed(1) + 's', 'num'),
+          cell(duration(p.age_secs), 'num'),
+        );
+        return row;
+      }));
+    }
+
+    function refresh() {
+      return fetch('api/tasks').then(r => r.json()).then(tasks => {
+        renderTabs(tasks.tabs);
+        renderProcesses(tasks.processes);
+      }).catch(() => {});
+    }
+
+    $('gcBtn').addEventListener('click', () => {
+      minimalist.invoke('collect_garbage').then(() => setTimeout(refresh, 500));
+    });
+    refresh();
+    setInterval(refresh, 2000);
+  </script>
 </body>
 </html>"#;
 
@@ -234,6 +944,935 @@ const FLASH_TEST_HTML: &str = r#"<!doctype html>
 </body>
 </html>"#;
 
+const HISTORY_HTML: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>History</title>
+  <style>
+    body {
+      margin: 0;
+      padding: 40px;
+      background: linear-gradient(135deg, #0f1720 0%, #071017 100%);
+      color: #e6eef3;
+      font-family: system-ui;
+    }
+    .container { max-width: 900px; margin: 0 auto; }
+    h1 { font-size: 28px; margin-bottom: 24px; }
+    h2 { font-size: 14px; color: #4fd1c5; margin: 24px 0 8px; }
+    .toolbar { display: flex; gap: 8px; margin-bottom: 16px; }
+    input, select {
+      background: rgba(11, 18, 32, 0.95);
+      border: 1px solid rgba(79, 209, 197, 0.2);
+      color: #e6eef3;
+      padding: 8px;
+      border-radius: 6px;
+    }
+    input { flex: 1; }
+    .day {
+      background: rgba(11, 18, 32, 0.8);
+      border-radius: 12px;
+      padding: 4px 16px;
+    }
+    .visit {
+      display: flex;
+      gap: 12px;
+      align-items: center;
+      padding: 8px 0;
+      border-bottom: 1px solid rgba(255, 255, 255, 0.05);
+    }
+    .visit:last-child { border: none; }
+    .time { font-size: 12px; color: #9aa6b2; width: 48px; }
+    .info { flex: 1; min-width: 0; }
+    .info a { color: #e6eef3; text-decoration: none; }
+    .url { font-size: 12px; color: #9aa6b2; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+    button {
+      background: rgba(79, 209, 197, 0.15);
+      color: #4fd1c5;
+      border: none;
+      padding: 6px 12px;
+      border-radius: 6px;
+      cursor: pointer;
+      font-weight: 600;
+    }
+    .empty { color: #9aa6b2; }
+  </style>
+  <script src="bridge.js"></script>
+</head>
+<body>
+  <div class="container">
+    <h1>History</h1>
+    <div class="toolbar">
+      <input id="search" type="search" placeholder="Search history">
+      <select id="range">
+        <option value="3600">Last hour</option>
+        <option value="86400">Last 24 hours</option>
+        <option value="604800">Last 7 days</option>
+        <option value="all">All time</option>
+      </select>
+      <button id="clear">Delete</button>
+    </div>
+    <p class="empty">Looking for something you read? <a href="minimalist:///search" style="color: #4fd1c5;">Search page content</a></p>
+    <div id="list"></div>
+  </div>
+  <script>
+    const $ = (id) => document.getElementById(id);
+
+    function render(visits) {
+      const list = $('list');
+      list.innerHTML = '';
+      if (visits.length === 0) {
+        list.innerHTML = '<p class="empty">No history found.</p>';
+        return;
+      }
+
+      let currentDay = null;
+      let group = null;
+      visits.forEach(v => {
+        const when = new Date(v.visited_at * 1000);
+        const day = when.toLocaleDateString(undefined, { weekday: 'long', year: 'numeric', month: 'long', day: 'numeric' });
+        if (day !== currentDay) {
+          currentDay = day;
+          const heading = document.createElement('h2');
+          heading.textContent = day;
+          group = document.createElement('div');
+          group.className = 'day';
+          list.append(heading, group);
+        }
+
+        const row = document.createElement('div');
+        row.className = 'visit';
+        const time = document.createElement('div');
+        time.className = 'time';
+        time.textContent = when.toLocaleTimeString(undefined, { hour: '2-digit', minute: '2-digit' });
+        const info = document.createElement('div');
+        info.className = 'info';
+        const link = document.createElement('a');
+        link.href = v.url;
+        link.textContent = v.title || v.url;
+        link.addEventListener('click', (e) => {
+          e.preventDefault();
+          minimalist.invoke('navigate', { url: v.url });
+        });
+        const url = document.createElement('div');
+        url.className = 'url';
+        url.textContent = v.url;
+        info.append(link, url);
+        const del = document.createElement('button');
+        del.textContent = '✕';
+        del.title = 'Remove from history';
+        del.addEventListener('click', () => minimalist.invoke('delete_history_visit', { id: v.id }).then(refresh));
+
+        row.append(time, info, del);
+        group.appendChild(row);
+      });
+    }
+
+    function refresh() {
+      minimalist.invoke('get_history', { query: $('search').value }).then(r => render(r.visits));
+    }
+
+    let debounce = null;
+    $('search').addEventListener('input', () => {
+      clearTimeout(debounce);
+      debounce = setTimeout(refresh, 200);
+    });
+
+    $('clear').addEventListener('click', () => {
+      const range = $('range').value;
+      const now = Math.floor(Date.now() / 1000);
+      const from = range === 'all' ? 0 : now - Number(range);
+      if (!confirm('Delete history from the selected time range?')) return;
+      minimalist.invoke('delete_history_range', { from, to: now + 1 }).then(refresh);
+    });
+
+    refresh();
+  </script>
+</body>
+</html>"#;
+
+const SEARCH_HTML: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Search Pages</title>
+  <style>
+    body {
+      margin: 0;
+      padding: 40px;
+      background: linear-gradient(135deg, #0f1720 0%, #071017 100%);
+      color: #e6eef3;
+      font-family: system-ui;
+    }
+    .container { max-width: 900px; margin: 0 auto; }
+    h1 { font-size: 28px; margin-bottom: 24px; }
+    .toolbar { display: flex; gap: 8px; margin-bottom: 16px; }
+    input, select {
+      background: rgba(11, 18, 32, 0.95);
+      border: 1px solid rgba(79, 209, 197, 0.2);
+      color: #e6eef3;
+      padding: 8px;
+      border-radius: 6px;
+    }
+    input { flex: 1; }
+    .result {
+      background: rgba(11, 18, 32, 0.8);
+      border-radius: 12px;
+      padding: 16px;
+      margin-bottom: 12px;
+    }
+    .result a { color: #e6eef3; text-decoration: none; font-weight: 600; }
+    .url { font-size: 12px; color: #9aa6b2; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+    .snippet { font-size: 14px; line-height: 1.5; margin-top: 8px; color: #c8d3dc; }
+    mark { background: rgba(79, 209, 197, 0.3); color: #e6eef3; border-radius: 2px; }
+    button {
+      background: rgba(79, 209, 197, 0.15);
+      color: #4fd1c5;
+      border: none;
+      padding: 6px 12px;
+      border-radius: 6px;
+      cursor: pointer;
+      font-weight: 600;
+    }
+    .empty { color: #9aa6b2; }
+  </style>
+  <script src="bridge.js"></script>
+</head>
+<body>
+  <div class="container">
+    <h1>Search Pages</h1>
+    <div class="toolbar">
+      <input id="query" type="search" placeholder="Search the text of pages you've visited" autofocus>
+      <select id="since">
+        <option value="all">Any time</option>
+        <option value="86400">Last 24 hours</option>
+        <option value="604800">Last week</option>
+        <option value="2592000">Last month</option>
+      </select>
+      <button id="clear">Clear index</button>
+    </div>
+    <div id="results"><p class="empty">Type to search.</p></div>
+  </div>
+  <script>
+    const $ = (id) => document.getElementById(id);
+
+    function render(results) {
+      const list = $('results');
+      list.innerHTML = '';
+      if (results.length === 0) {
+        list.innerHTML = '<p class="empty">No matching pages.</p>';
+        return;
+      }
+      results.forEach(r => {
+        const item = document.createElement('div');
+        item.className = 'result';
+        const link = document.createElement('a');
+        link.href = r.url;
+        link.textContent = r.title || r.url;
+        link.addEventListener('click', (e) => {
+          e.preventDefault();
+          minimalist.invoke('navigate', { url: r.url });
+        });
+        const url = document.createElement('div');
+        url.className = 'url';
+        url.textContent = r.url + ' · ' + new Date(r.indexed_at * 1000).toLocaleDateString();
+        const snippet = document.createElement('div');
+        snippet.className = 'snippet';
+        // Escaped by the browser core; only <mark> tags are added.
+        snippet.innerHTML = r.snippet;
+        item.append(link, url, snippet);
+        list.appendChild(item);
+      });
+    }
+
+    function search() {
+      const query = $('query').value.trim();
+      if (!query) {
+        $('results').innerHTML = '<p class="empty">Type to search.</p>';
+        return;
+      }
+      const range = $('since').value;
+      const since = range === 'all' ? null : Math.floor(Date.now() / 1000) - Number(range);
+      minimalist.invoke('search_content', { query, since }).then(r => render(r.results));
+    }
+
+    let debounce = null;
+    $('query').addEventListener('input', () => {
+      clearTimeout(debounce);
+      debounce = setTimeout(search, 200);
+    });
+    $('since').addEventListener('change', search);
+
+    $('clear').addEventListener('click', () => {
+      if (!confirm('Forget the text of every indexed page?')) return;
+      minimalist.invoke('clear_content_index').then(search);
+    });
+
+    const initial = new URLSearchParams(location.search).get('q');
+    if (initial) {
+      $('query').value = initial;
+      search();
+    }
+  </script>
+</body>
+</html>"#;
+
+const REDIRECTS_HTML: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Redirects</title>
+  <style>
+    body {
+      margin: 0;
+      padding: 40px;
+      background: linear-gradient(135deg, #0f1720 0%, #071017 100%);
+      color: #e6eef3;
+      font-family: system-ui;
+    }
+    .container { max-width: 900px; margin: 0 auto; }
+    h1 { font-size: 28px; margin-bottom: 8px; }
+    h2 { font-size: 18px; color: #4fd1c5; margin: 0 0 12px; }
+    .hint { color: #9aa6b2; font-size: 14px; line-height: 1.5; margin-bottom: 24px; }
+    code { background: rgba(79, 209, 197, 0.1); padding: 1px 4px; border-radius: 4px; }
+    .section {
+      background: rgba(11, 18, 32, 0.8);
+      border-radius: 12px;
+      padding: 20px;
+      margin-bottom: 20px;
+    }
+    .rule {
+      display: flex;
+      align-items: center;
+      gap: 12px;
+      padding: 10px 0;
+      border-bottom: 1px solid rgba(255, 255, 255, 0.05);
+    }
+    .rule:last-child { border: none; }
+    .rule .info { flex: 1; min-width: 0; }
+    .rule .detail { font-size: 12px; color: #9aa6b2; word-break: break-all; }
+    .rule.disabled .info { opacity: 0.5; }
+    .form { display: flex; gap: 8px; flex-wrap: wrap; }
+    input[type="text"], select {
+      background: rgba(11, 18, 32, 0.95);
+      border: 1px solid rgba(79, 209, 197, 0.2);
+      color: #e6eef3;
+      padding: 8px;
+      border-radius: 6px;
+    }
+    .form input[type="text"] { flex: 1; min-width: 160px; }
+    button {
+      background: rgba(79, 209, 197, 0.15);
+      color: #4fd1c5;
+      border: none;
+      padding: 6px 12px;
+      border-radius: 6px;
+      cursor: pointer;
+      font-weight: 600;
+    }
+    .error { color: #ff6b6b; font-size: 13px; min-height: 1em; margin-top: 8px; }
+    .result { font-size: 14px; margin-top: 8px; word-break: break-all; min-height: 1em; }
+    .empty { color: #9aa6b2; }
+  </style>
+  <script src="bridge.js"></script>
+</head>
+<body>
+  <div class="container">
+    <h1>Redirects</h1>
+    <p class="hint">
+      Rewrite addresses before they load. In a glob, <code>*</code> matches anything and each
+      becomes <code>$1</code>, <code>$2</code>... in the target; everything else, <code>?</code>
+      included, matches as written.
+      Regex rules may use <code>$1</code> or <code>${name}</code>. Write <code>${1}</code> when a
+      letter or digit follows the reference. The first enabled rule that matches wins.
+    </p>
+    <div class="section">
+      <h2>Rules</h2>
+      <div id="rules"></div>
+    </div>
+    <div class="section">
+      <h2 id="formTitle">Add a rule</h2>
+      <div class="form">
+        <input type="text" id="name" placeholder="Name">
+        <select id="kind">
+          <option value="glob">Glob</option>
+          <option value="regex">Regex</option>
+        </select>
+      </div>
+      <div class="form" style="margin-top: 8px;">
+        <input type="text" id="pattern" placeholder="Pattern, e.g. https://www.reddit.com/*">
+        <input type="text" id="target" placeholder="Target, e.g. https://old.reddit.com/$1">
+      </div>
+      <div class="form" style="margin-top: 8px;">
+        <button id="save">Save</button>
+        <button id="cancel" hidden>Cancel</button>
+      </div>
+      <div class="error" id="error"></div>
+    </div>
+    <div class="section">
+      <h2>Test</h2>
+      <div class="form">
+        <input type="text" id="testUrl" placeholder="https://www.reddit.com/r/rust/">
+        <button id="testForm">Test rule above</button>
+        <button id="testAll">Test enabled rules</button>
+      </div>
+      <div class="result" id="result"></div>
+    </div>
+  </div>
+  <script>
+    const $ = (id) => document.getElementById(id);
+    let editingId = 0;
+    let rules = [];
+
+    function formRule() {
+      return {
+        id: editingId,
+        name: $('name').value,
+        kind: $('kind').value,
+        pattern: $('pattern').value,
+        target: $('target').value,
+        enabled: true,
+      };
+    }
+
+    function resetForm() {
+      editingId = 0;
+      $('name').value = $('pattern').value = $('target').value = '';
+      $('kind').value = 'glob';
+      $('formTitle').textContent = 'Add a rule';
+      $('cancel').hidden = true;
+      $('error').textContent = '';
+    }
+
+    function render(rules) {
+      const list = $('rules');
+      list.innerHTML = '';
+      if (rules.length === 0) {
+        list.innerHTML = '<p class="empty">No redirect rules yet.</p>';
+        return;
+      }
+      rules.forEach(rule => {
+        const row = document.createElement('div');
+        row.className = 'rule' + (rule.enabled ? '' : ' disabled');
+        const toggle = document.createElement('input');
+        toggle.type = 'checkbox';
+        toggle.checked = rule.enabled;
+        toggle.title = 'Enabled';
+        toggle.addEventListener('change', () => {
+          minimalist.invoke('set_redirect_enabled', { id: rule.id, enabled: toggle.checked })
+            .then(r => load(r.rules));
+        });
+        const info = document.createElement('div');
+        info.className = 'info';
+        const name = document.createElement('div');
+        name.textContent = rule.name;
+        const detail = document.createElement('div');
+        detail.className = 'detail';
+        detail.textContent = rule.kind + ': ' + rule.pattern + ' → ' + rule.target;
+        info.append(name, detail);
+        const editBtn = document.createElement('button');
+        editBtn.textContent = 'Edit';
+        editBtn.addEventListener('click', () => {
+          editingId = rule.id;
+          $('name').value = rule.name;
+          $('kind').value = rule.kind;
+          $('pattern').value = rule.pattern;
+          $('target').value = rule.target;
+          $('formTitle').textContent = 'Edit rule';
+          $('cancel').hidden = false;
+        });
+        const removeBtn = document.createElement('button');
+        removeBtn.textContent = 'Remove';
+        removeBtn.addEventListener('click', () => {
+          minimalist.invoke('remove_redirect', { id: rule.id }).then(r => {
+            if (editingId === rule.id) resetForm();
+            load(r.rules);
+          });
+        });
+        row.append(toggle, info, editBtn, removeBtn);
+        list.appendChild(row);
+      });
+    }
+
+    $('save').addEventListener('click', () => {
+      const rule = formRule();
+      if (editingId) {
+        const existing = rules.find(r => r.id === editingId);
+        if (existing) rule.enabled = existing.enabled;
+      }
+      minimalist.invoke('save_redirect', rule)
+        .then(r => { resetForm(); load(r.rules); })
+        .catch(err => { $('error').textContent = err.message; });
+    });
+    $('cancel').addEventListener('click', resetForm);
+
+    function showTest(promise) {
+      $('result').textContent = '';
+      promise
+        .then(r => {
+          $('result').textContent = r.url ? '→ ' + r.url + ' (' + (r.rule || 'this rule') + ')' : 'No rule matches.';
+        })
+        .catch(err => { $('result').textContent = err.message; });
+    }
+    $('testForm').addEventListener('click', () => {
+      showTest(minimalist.invoke('test_redirect', { url: $('testUrl').value, rule: formRule() }));
+    });
+    $('testAll').addEventListener('click', () => {
+      showTest(minimalist.invoke('test_redirect', { url: $('testUrl').value }));
+    });
+
+    function load(list) {
+      rules = list;
+      render(list);
+    }
+    minimalist.invoke('get_redirects').then(r => load(r.rules));
+  </script>
+</body>
+</html>"#;
+
+const BOOKMARKS_HTML: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Bookmarks</title>
+  <style>
+    body {
+      margin: 0;
+      padding: 40px;
+      background: linear-gradient(135deg, #0f1720 0%, #071017 100%);
+      color: #e6eef3;
+      font-family: system-ui;
+    }
+    .container { max-width: 1100px; margin: 0 auto; }
+    h1 { font-size: 28px; margin-bottom: 24px; }
+    .layout { display: grid; grid-template-columns: 240px 1fr; gap: 16px; }
+    .panel {
+      background: rgba(11, 18, 32, 0.8);
+      border-radius: 12px;
+      padding: 16px;
+    }
+    .folder { padding: 6px 8px; border-radius: 6px; cursor: pointer; color: #9aa6b2; }
+    .folder.selected { background: rgba(79, 209, 197, 0.2); color: #4fd1c5; }
+    .bookmark {
+      display: flex;
+      gap: 12px;
+      align-items: center;
+      padding: 10px 0;
+      border-bottom: 1px solid rgba(255, 255, 255, 0.05);
+    }
+    .bookmark:last-child { border: none; }
+    .bookmark .info { flex: 1; min-width: 0; }
+    .bookmark a { color: #e6eef3; text-decoration: none; }
+    .url, .tags { font-size: 12px; color: #9aa6b2; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+    .tags span { background: rgba(79, 209, 197, 0.15); color: #4fd1c5; border-radius: 4px; padding: 0 6px; margin-right: 4px; }
+    form { display: grid; grid-template-columns: 1fr 1fr; gap: 8px; margin-bottom: 16px; }
+    input, select {
+      background: rgba(11, 18, 32, 0.95);
+      border: 1px solid rgba(79, 209, 197, 0.2);
+      color: #e6eef3;
+      padding: 8px;
+      border-radius: 6px;
+    }
+    button {
+      background: #4fd1c5;
+      color: #0f1720;
+      border: none;
+      padding: 8px 14px;
+      border-radius: 6px;
+      cursor: pointer;
+      font-weight: 600;
+    }
+    button.secondary { background: rgba(79, 209, 197, 0.15); color: #4fd1c5; }
+    .error { color: #ff6b6b; min-height: 20px; }
+  </style>
+  <script src="bridge.js"></script>
+</head>
+<body>
+  <div class="container">
+    <h1>Bookmarks</h1>
+    <div class="layout">
+      <div class="panel">
+        <div id="folders"></div>
+        <p><button class="secondary" id="newFolder">New Folder</button></p>
+        <p><button class="secondary" id="renameFolder">Rename</button>
+           <button class="secondary" id="deleteFolder">Delete</button></p>
+        <p><button class="secondary" id="importBtn">Import…</button>
+           <button class="secondary" id="exportBtn">Export</button></p>
+        <input type="file" id="importFile" accept=".html,.htm,.json" hidden>
+        <div class="url" id="transferStatus"></div>
+      </div>
+      <div class="panel">
+        <form id="editor">
+          <input id="title" placeholder="Title">
+          <input id="url" placeholder="URL" required>
+          <input id="tags" placeholder="Tags (comma separated)">
+          <select id="folder"></select>
+          <div>
+            <button type="submit" id="save">Add Bookmark</button>
+            <button type="button" class="secondary" id="cancel">Cancel</button>
+          </div>
+        </form>
+        <div class="error" id="error"></div>
+        <div id="list"></div>
+      </div>
+    </div>
+  </div>
+  <script>
+    let store = { folders: [], bookmarks: [] };
+    let selectedFolder = null;
+    let editingId = null;
+
+    const $ = (id) => document.getElementById(id);
+
+    function call(cmd, args) {
+      $('error').textContent = '';
+      return minimalist.invoke(cmd, args)
+        .then(reply => { store = reply; render(); })
+        .catch(err => { $('error').textContent = err.message; });
+    }
+
+    function folderPath(id) {
+      const parts = [];
+      for (let f = store.folders.find(x => x.id === id); f; f = store.folders.find(x => x.id === f.parent_id)) {
+        parts.unshift(f.name);
+      }
+      return parts.join(' / ');
+    }
+
+    function renderFolders() {
+      const box = $('folders');
+      box.innerHTML = '';
+      const add = (id, name, depth) => {
+        const el = document.createElement('div');
+        el.className = 'folder' + (id === selectedFolder ? ' selected' : '');
+        el.style.paddingLeft = (8 + depth * 14) + 'px';
+        el.textContent = name;
+        el.addEventListener('click', () => { selectedFolder = id; render(); });
+        box.appendChild(el);
+        store.folders.filter(f => id !== null && f.parent_id === id)
+          .forEach(f => add(f.id, f.name, depth + 1));
+      };
+      add(null, 'All Bookmarks', 0);
+      store.folders.filter(f => f.parent_id === null).forEach(f => add(f.id, f.name, 1));
+
+      const select = $('folder');
+      const current = select.value;
+      select.innerHTML = '<option value="">(Top level)</option>';
+      store.folders.forEach(f => {
+        const opt = document.createElement('option');
+        opt.value = f.id;
+        opt.textContent = folderPath(f.id);
+        select.appendChild(opt);
+      });
+      select.value = current;
+    }
+
+    function renderList() {
+      const list = $('list');
+      list.innerHTML = '';
+      store.bookmarks
+        .filter(b => selectedFolder === null || b.folder_id === selectedFolder)
+        .forEach(b => {
+          const row = document.createElement('div');
+          row.className = 'bookmark';
+          const info = document.createElement('div');
+          info.className = 'info';
+          const link = document.createElement('a');
+          link.href = b.url;
+          link.textContent = b.title;
+          link.addEventListener('click', (e) => {
+            e.preventDefault();
+            minimalist.invoke('navigate', { url: b.url });
+          });
+          const url = document.createElement('div');
+          url.className = 'url';
+          url.textContent = b.url + (b.folder_id ? '  ·  ' + folderPath(b.folder_id) : '');
+          const tags = document.createElement('div');
+          tags.className = 'tags';
+          b.tags.forEach(t => {
+            const tag = document.createElement('span');
+            tag.textContent = t;
+            tags.appendChild(tag);
+          });
+          info.append(link, url, tags);
+
+          const edit = document.createElement('button');
+          edit.className = 'secondary';
+          edit.textContent = 'Edit';
+          edit.addEventListener('click', () => startEdit(b));
+          const del = document.createElement('button');
+          del.className = 'secondary';
+          del.textContent = 'Delete';
+          del.addEventListener('click', () => call('delete_bookmark', { id: b.id }));
+
+          row.append(info, edit, del);
+          list.appendChild(row);
+        });
+    }
+
+    function render() {
+      renderFolders();
+      renderList();
+    }
+
+    function startEdit(b) {
+      editingId = b.id;
+      $('title').value = b.title;
+      $('url').value = b.url;
+      $('tags').value = b.tags.join(', ');
+      $('folder').value = b.folder_id ?? '';
+      $('save').textContent = 'Save Changes';
+    }
+
+    function resetEditor() {
+      editingId = null;
+      $('editor').reset();
+      $('folder').value = selectedFolder ?? '';
+      $('save').textContent = 'Add Bookmark';
+    }
+
+    $('editor').addEventListener('submit', (e) => {
+      e.preventDefault();
+      const title = $('title').value;
+      const url = $('url').value;
+      const tags = $('tags').value.split(',');
+      const folderId = $('folder').value === '' ? null : Number($('folder').value);
+
+      const done = editingId === null
+        ? call('add_bookmark', { url, title, folder_id: folderId, tags })
+        : call('update_bookmark', { id: editingId, title, url, tags })
+            .then(() => call('move_bookmark', { id: editingId, folder_id: folderId }));
+      done.then(resetEditor);
+    });
+    $('cancel').addEventListener('click', resetEditor);
+
+    $('newFolder').addEventListener('click', () => {
+      const name = prompt('Folder name');
+      if (name) call('add_bookmark_folder', { name, parent_id: selectedFolder });
+    });
+    $('renameFolder').addEventListener('click', () => {
+      if (selectedFolder === null) return;
+      const name = prompt('New folder name', folderPath(selectedFolder).split(' / ').pop());
+      if (name) call('rename_bookmark_folder', { id: selectedFolder, name });
+    });
+    $('deleteFolder').addEventListener('click', () => {
+      if (selectedFolder === null || !confirm('Delete this folder and everything in it?')) return;
+      const id = selectedFolder;
+      selectedFolder = null;
+      call('delete_bookmark_folder', { id });
+    });
+
+    $('importBtn').addEventListener('click', () => $('importFile').click());
+    $('importFile').addEventListener('change', () => {
+      const file = $('importFile').files[0];
+      if (!file) return;
+      file.text()
+        .then(content => minimalist.invoke('import_bookmarks', { content }))
+        .then(reply => {
+          store = reply.store;
+          render();
+          const s = reply.summary;
+          $('transferStatus').textContent =
+            `Imported ${s.added} bookmark(s), ${s.folders_created} folder(s); skipped ${s.duplicates} duplicate(s).`;
+        })
+        .catch(err => { $('error').textContent = err.message; })
+        .finally(() => { $('importFile').value = ''; });
+    });
+
+    $('exportBtn').addEventListener('click', () => {
+      minimalist.invoke('export_bookmarks').then(reply => {
+        const link = document.createElement('a');
+        link.href = URL.createObjectURL(new Blob([reply.html], { type: 'text/html' }));
+        link.download = 'bookmarks.html';
+        link.click();
+        URL.revokeObjectURL(link.href);
+        $('transferStatus').textContent = reply.path ? `Also saved to ${reply.path}` : '';
+      });
+    });
+
+    call('get_bookmarks', {});
+  </script>
+</body>
+</html>"#;
+
+const RESTORE_HTML: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Restore Session</title>
+  <style>
+    body {
+      margin: 0;
+      padding: 40px;
+      background: linear-gradient(135deg, #0f1720 0%, #071017 100%);
+      color: #e6eef3;
+      font-family: system-ui;
+    }
+    .container { max-width: 800px; margin: 0 auto; }
+    h1 { font-size: 28px; margin-bottom: 8px; }
+    p { color: #9aa6b2; line-height: 1.6; }
+    .tabs {
+      background: rgba(11, 18, 32, 0.8);
+      border-radius: 12px;
+      padding: 8px 24px;
+      margin: 24px 0;
+    }
+    label {
+      display: flex;
+      gap: 12px;
+      align-items: center;
+      padding: 12px 0;
+      border-bottom: 1px solid rgba(255, 255, 255, 0.05);
+      cursor: pointer;
+    }
+    label:last-child { border: none; }
+    .url { font-size: 12px; color: #9aa6b2; }
+    button {
+      background: #4fd1c5;
+      color: #0f1720;
+      border: none;
+      padding: 10px 20px;
+      border-radius: 8px;
+      cursor: pointer;
+      font-weight: 600;
+      margin-right: 8px;
+    }
+    button.secondary { background: rgba(79, 209, 197, 0.15); color: #4fd1c5; }
+  </style>
+  <script src="bridge.js"></script>
+</head>
+<body>
+  <div class="container">
+    <h1>Restore Session</h1>
+    <p>The browser didn't shut down properly last time. Choose which tabs to reopen.</p>
+    <div class="tabs" id="tabs"></div>
+    <button id="restoreSelected">Restore Selected</button>
+    <button id="restoreAll" class="secondary">Restore All</button>
+    <button id="startFresh" class="secondary">Start Fresh</button>
+  </div>
+  <script>
+    const list = document.getElementById('tabs');
+
+    function render(tabs) {
+      list.innerHTML = '';
+      if (tabs.length === 0) {
+        list.innerHTML = '<p>No tabs left to restore.</p>';
+        return;
+      }
+      tabs.forEach(tab => {
+        const row = document.createElement('label');
+        const box = document.createElement('input');
+        box.type = 'checkbox';
+        box.checked = true;
+        box.value = tab.id;
+        const text = document.createElement('div');
+        const title = document.createElement('div');
+        title.textContent = tab.title;
+        const url = document.createElement('div');
+        url.className = 'url';
+        url.textContent = tab.display_url;
+        text.append(title, url);
+        row.append(box, text);
+        list.appendChild(row);
+      });
+    }
+
+    function refresh() {
+      minimalist.invoke('get_recovered_tabs').then(r => render(r.tabs));
+    }
+
+    function restore(ids) {
+      minimalist.invoke('restore_tabs', { tab_ids: ids }).then(refresh);
+    }
+
+    document.getElementById('restoreSelected').addEventListener('click', () => {
+      restore([...list.querySelectorAll('input:checked')].map(b => Number(b.value)));
+    });
+    document.getElementById('restoreAll').addEventListener('click', () => {
+      restore([...list.querySelectorAll('input')].map(b => Number(b.value)));
+    });
+    document.getElementById('startFresh').addEventListener('click', () => {
+      minimalist.invoke('discard_recovered').then(refresh);
+    });
+
+    refresh();
+  </script>
+</body>
+</html>"#;
+
+const HTTPS_ONLY_HTML: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>HTTPS Not Available</title>
+  <style>
+    body {
+      margin: 0;
+      padding: 40px;
+      background: linear-gradient(135deg, #0f1720 0%, #071017 100%);
+      color: #e6eef3;
+      font-family: system-ui;
+      display: flex;
+      align-items: center;
+      justify-content: center;
+      min-height: 100vh;
+      box-sizing: border-box;
+    }
+    .container { max-width: 560px; }
+    h1 { font-size: 28px; margin-bottom: 16px; }
+    p { color: #9aa6b2; line-height: 1.6; }
+    .url { color: #e6eef3; word-break: break-all; font-family: monospace; }
+    .actions { display: flex; gap: 8px; flex-wrap: wrap; margin-top: 24px; }
+    button {
+      background: rgba(79, 209, 197, 0.15);
+      color: #4fd1c5;
+      border: none;
+      padding: 10px 16px;
+      border-radius: 6px;
+      cursor: pointer;
+      font-weight: 600;
+    }
+    button.primary { background: #4fd1c5; color: #0f1720; }
+    .error { color: #ff6b6b; font-size: 13px; min-height: 1em; margin-top: 12px; }
+  </style>
+  <script src="bridge.js"></script>
+</head>
+<body>
+  <div class="container">
+    <h1>Secure connection not available</h1>
+    <p>HTTPS-Only mode tried to load a secure version of <span class="url" id="url"></span>, but the site didn't answer over HTTPS.</p>
+    <p>Over plain HTTP, anyone on the network can see or change what you send and receive.</p>
+    <div class="actions">
+      <button class="primary" id="back">Go back</button>
+      <button id="once">Continue over HTTP once</button>
+      <button id="always">Always allow HTTP for <span id="host"></span></button>
+    </div>
+    <div class="error" id="error"></div>
+  </div>
+  <script>
+    const $ = (id) => document.getElementById(id);
+    const url = new URLSearchParams(location.search).get('url') || '';
+    $('url').textContent = url;
+    try {
+      $('host').textContent = new URL(url).host;
+    } catch (e) {
+      $('host').textContent = 'this site';
+    }
+
+    function proceed(always) {
+      minimalist.invoke('continue_over_http', { url, always })
+        .catch(err => { $('error').textContent = err.message; });
+    }
+    $('once').addEventListener('click', () => proceed(false));
+    $('always').addEventListener('click', () => proceed(true));
+    $('back').addEventListener('click', () => {
+      minimalist.invoke('go', { delta: -1 })
+        .catch(() => minimalist.invoke('navigate', { url: 'minimalist://newtab' }));
+    });
+  </script>
+</body>
+</html>"#;
+
 const ERROR_HTML: &str = r#"<!doctype html>
 <html lang="en">
 <head>
@@ -258,8 +1897,16 @@ const ERROR_HTML: &str = r#"<!doctype html>
 </head>
 <body>
   <div class="container">
-    <h1>Page Not Found</h1>
-    <p>The page you're looking for couldn't be loaded.</p>
+    <h1 id="heading">Page Not Found</h1>
+    <p id="message">The page you're looking for couldn't be loaded.</p>
   </div>
+  <script>
+    // Address-bar input that can't be opened lands here with ?reason=
+    const reason = new URLSearchParams(location.search).get('reason');
+    if (reason) {
+      document.getElementById('heading').textContent = "Can't Open This Address";
+      document.getElementById('message').textContent = reason;
+    }
+  </script>
 </body>
 </html>"#;
\ No newline at end of file
@@ -0,0 +1,255 @@
+// bangs.rs - `!gh query` shortcuts resolved locally from bundled and user bangs
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use lazy_static::lazy_static;
+use url::Url;
+use crate::search_engines::{fill_template, normalize_template, SEARCH_TERMS};
+use crate::storage;
+
+const BANGS_FILE: &str = "bangs.json";
+
+lazy_static! {
+    static ref DEFAULT_BANGS: Vec<Bang> = serde_json::from_str(include_str!("assets/bangs.json"))
+        .expect("bundled bangs.json is valid");
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Bang {
+    /// Typed after `!`, without it.
+    pub trigger: String,
+    pub name: String,
+    pub url_template: String,
+}
+
+impl Bang {
+    /// An empty query goes to the site itself, like DuckDuckGo does.
+    pub fn url_for(&self, query: &str) -> String {
+        if query.is_empty() {
+            let site = Url::parse(&self.url_template.replace(SEARCH_TERMS, ""))
+                .ok()
+                .map(|u| u.origin().ascii_serialization())
+                .filter(|origin| origin != "null");
+            if let Some(site) = site {
+                return format!("{}/", site);
+            }
+        }
+        fill_template(&self.url_template, query)
+    }
+}
+
+/// A bang as listed on the settings page.
+#[derive(Serialize, Clone, Debug)]
+pub struct BangEntry {
+    #[serde(flatten)]
+    pub bang: Bang,
+    /// Comes from the bundled set (possibly overridden by the user).
+    pub builtin: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BangError {
+    InvalidTrigger,
+    MissingSearchTerms,
+    NotFound(String),
+}
+
+impl fmt::Display for BangError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BangError::InvalidTrigger => write!(f, "Bang triggers must be a single word"),
+            BangError::MissingSearchTerms => write!(f, "Bang URL must contain {} or %s", SEARCH_TERMS),
+            BangError::NotFound(trigger) => write!(f, "No bang !{}", trigger),
+        }
+    }
+}
+
+/// User changes on top of the bundled set, persisted in browser_data/bangs.json.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct BangTable {
+    /// Added bangs and overrides of bundled ones, by trigger.
+    custom: Vec<Bang>,
+    /// Bundled triggers the user removed.
+    removed: Vec<String>,
+}
+
+impl BangTable {
+    pub fn load() -> Self {
+        match storage::load_json(BANGS_FILE) {
+            Ok(table) => table.unwrap_or_default(),
+            Err(e) => {
+                log::warn!("Ignoring unreadable bangs: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save_json(BANGS_FILE, self) {
+            log::warn!("Failed to save bangs: {}", e);
+        }
+    }
+
+    pub fn find(&self, trigger: &str) -> Option<&Bang> {
+        let trigger = trigger.to_lowercase();
+        self.custom
+            .iter()
+            .find(|b| b.trigger == trigger)
+            .or_else(|| {
+                if self.removed.contains(&trigger) {
+                    None
+                } else {
+                    DEFAULT_BANGS.iter().find(|b| b.trigger == trigger)
+                }
+            })
+    }
+
+    /// Every active bang, sorted by trigger.
+    pub fn entries(&self) -> Vec<BangEntry> {
+        let mut entries: Vec<BangEntry> = DEFAULT_BANGS
+            .iter()
+            .filter(|b| !self.removed.contains(&b.trigger))
+            .filter(|b| !self.custom.iter().any(|c| c.trigger == b.trigger))
+            .map(|b| BangEntry { bang: b.clone(), builtin: true })
+            .collect();
+        entries.extend(self.custom.iter().map(|b| BangEntry {
+            bang: b.clone(),
+            builtin: DEFAULT_BANGS.iter().any(|d| d.trigger == b.trigger),
+        }));
+        entries.sort_by(|a, b| a.bang.trigger.cmp(&b.bang.trigger));
+        entries
+    }
+
+    /// Splits `!gh tokio` or `tokio !gh` into the bang and the remaining query.
+    pub fn resolve(&self, input: &str) -> Option<(&Bang, String)> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let (position, bang) = [0, words.len().checked_sub(1)?]
+            .into_iter()
+            .find_map(|i| {
+                let trigger = words[i].strip_prefix('!')?;
+                self.find(trigger).map(|bang| (i, bang))
+            })?;
+        let query = words
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != position)
+            .map(|(_, w)| *w)
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some((bang, query))
+    }
+
+    /// Adds a bang, or replaces the one with the same trigger.
+    pub fn set(&mut self, mut bang: Bang) -> Result<(), BangError> {
+        bang.trigger = bang.trigger.trim().trim_start_matches('!').to_lowercase();
+        if bang.trigger.is_empty() || bang.trigger.contains(char::is_whitespace) {
+            return Err(BangError::InvalidTrigger);
+        }
+        bang.url_template = normalize_template(&bang.url_template)
+            .map_err(|_| BangError::MissingSearchTerms)?;
+        bang.name = match bang.name.trim() {
+            "" => bang.trigger.clone(),
+            name => name.to_string(),
+        };
+        self.removed.retain(|t| *t != bang.trigger);
+        self.custom.retain(|b| b.trigger != bang.trigger);
+        self.custom.push(bang);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, trigger: &str) -> Result<(), BangError> {
+        let trigger = trigger.trim_start_matches('!').to_lowercase();
+        if self.find(&trigger).is_none() {
+            return Err(BangError::NotFound(trigger));
+        }
+        self.custom.retain(|b| b.trigger != trigger);
+        if DEFAULT_BANGS.iter().any(|b| b.trigger == trigger) {
+            self.removed.push(trigger);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bang(trigger: &str, url_template: &str) -> Bang {
+        Bang { trigger: trigger.to_string(), name: String::new(), url_template: url_template.to_string() }
+    }
+
+    fn resolved(table: &BangTable, input: &str) -> Option<String> {
+        table.resolve(input).map(|(bang, query)| bang.url_for(&query))
+    }
+
+    #[test]
+    fn resolves() {
+        let table = BangTable::default();
+        let cases = [
+            // At the start or the end
+            ("!gh tokio", Some("https://github.com/search?q=tokio")),
+            ("tokio !gh", Some("https://github.com/search?q=tokio")),
+            ("!GH tokio runtime", Some("https://github.com/search?q=tokio%20runtime")),
+            ("  tokio   runtime  !gh ", Some("https://github.com/search?q=tokio%20runtime")),
+            ("!gh", Some("https://github.com/")),
+            ("!w a&b", Some("https://en.wikipedia.org/wiki/Special:Search?search=a%26b")),
+            // The first word wins when both ends are bangs
+            ("!gh tokio !w", Some("https://github.com/search?q=tokio%20%21w")),
+            // Not a bang
+            ("tokio !gh runtime", None),
+            ("!nope tokio", None),
+            ("gh tokio", None),
+            ("tokio!gh", None),
+            ("!", None),
+            ("", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(resolved(&table, input).as_deref(), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn custom_bangs_override_bundled_ones() {
+        let mut table = BangTable::default();
+        table.set(bang(" !GH ", "https://example.com/gh?q=%s")).unwrap();
+        table.set(bang("jira", "https://jira.example.com/search?q={searchTerms}")).unwrap();
+        assert_eq!(resolved(&table, "!gh tokio").as_deref(), Some("https://example.com/gh?q=tokio"));
+        assert_eq!(resolved(&table, "bug !jira").as_deref(), Some("https://jira.example.com/search?q=bug"));
+
+        let entries = table.entries();
+        let gh = entries.iter().find(|e| e.bang.trigger == "gh").unwrap();
+        assert!(gh.builtin);
+        assert_eq!(gh.bang.name, "gh");
+        assert!(!entries.iter().find(|e| e.bang.trigger == "jira").unwrap().builtin);
+        assert!(entries.windows(2).all(|w| w[0].bang.trigger < w[1].bang.trigger));
+    }
+
+    #[test]
+    fn removal() {
+        let mut table = BangTable::default();
+        table.set(bang("gh", "https://example.com/gh?q=%s")).unwrap();
+        // Removing an override removes the bundled bang too
+        table.remove("!gh").unwrap();
+        assert_eq!(table.find("gh"), None);
+        assert_eq!(table.remove("gh"), Err(BangError::NotFound("gh".to_string())));
+        // Setting it again brings it back
+        table.set(bang("gh", "https://github.com/search?q=%s")).unwrap();
+        assert!(table.find("gh").is_some());
+    }
+
+    #[test]
+    fn set_validates() {
+        let mut table = BangTable::default();
+        let cases = [
+            (bang("", "https://a.com/?q=%s"), Err(BangError::InvalidTrigger)),
+            (bang("!", "https://a.com/?q=%s"), Err(BangError::InvalidTrigger)),
+            (bang("two words", "https://a.com/?q=%s"), Err(BangError::InvalidTrigger)),
+            (bang("a", "https://a.com/"), Err(BangError::MissingSearchTerms)),
+            (bang("a", "https://a.com/?q=%s"), Ok(())),
+        ];
+        for (bang, expected) in cases {
+            let trigger = bang.trigger.clone();
+            assert_eq!(table.set(bang), expected, "{:?}", trigger);
+        }
+    }
+}
@@ -0,0 +1,546 @@
+// bookmark_transfer.rs - Importing and exporting bookmarks from other browsers
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use crate::bookmarks::BookmarkStore;
+use crate::storage;
+use crate::tab_history::now_secs;
+
+/// Seconds between 1601-01-01 (Chrome's epoch) and 1970-01-01.
+const CHROME_EPOCH_OFFSET: u64 = 11_644_473_600;
+const EXPORT_FILE: &str = "bookmarks_export.html";
+
+// Every browser's special root folders land in the same place here.
+const TOOLBAR_FOLDER: &str = "Bookmarks Bar";
+const MENU_FOLDER: &str = "Bookmarks Menu";
+const OTHER_FOLDER: &str = "Other Bookmarks";
+const MOBILE_FOLDER: &str = "Mobile Bookmarks";
+
+lazy_static! {
+    static ref NETSCAPE_TOKEN: Regex = Regex::new(
+        r"(?is)<(/?)dl\b[^>]*>|<h3\b([^>]*)>(.*?)</h3>|<a\b([^>]*)>(.*?)</a>"
+    ).unwrap();
+    static ref HTML_ATTRIBUTE: Regex = Regex::new(r#"(?i)([a-z_]+)\s*=\s*"([^"]*)""#).unwrap();
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BookmarkFormat {
+    NetscapeHtml,
+    ChromeJson,
+    FirefoxJson,
+}
+
+impl BookmarkFormat {
+    pub fn detect(content: &str) -> Option<Self> {
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('<') {
+            return Some(BookmarkFormat::NetscapeHtml);
+        }
+        let json: Value = serde_json::from_str(trimmed).ok()?;
+        if json.get("roots").is_some() {
+            Some(BookmarkFormat::ChromeJson)
+        } else if json.get("type").and_then(Value::as_str) == Some("text/x-moz-place-container") {
+            Some(BookmarkFormat::FirefoxJson)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TransferError {
+    UnknownFormat,
+    InvalidJson(serde_json::Error),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferError::UnknownFormat => write!(f, "Unrecognized bookmark file format"),
+            TransferError::InvalidJson(e) => write!(f, "Invalid bookmark JSON: {}", e),
+        }
+    }
+}
+
+/// Format-independent bookmark tree produced by the parsers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportNode {
+    Folder {
+        name: String,
+        children: Vec<ImportNode>,
+    },
+    Bookmark {
+        title: String,
+        url: String,
+        tags: Vec<String>,
+        added: Option<u64>,
+    },
+}
+
+#[derive(Serialize, Debug, Default, Clone)]
+pub struct ImportSummary {
+    pub format: Option<BookmarkFormat>,
+    pub added: usize,
+    pub duplicates: usize,
+    pub folders_created: usize,
+}
+
+/// Detects the format of `content` and merges it into `store`. Folders with
+/// the same name and parent are reused; URLs already bookmarked are skipped.
+pub fn import(store: &mut BookmarkStore, content: &str) -> Result<ImportSummary, TransferError> {
+    let format = BookmarkFormat::detect(content).ok_or(TransferError::UnknownFormat)?;
+    let nodes = parse(content, format)?;
+
+    let mut summary = ImportSummary {
+        format: Some(format),
+        ..Default::default()
+    };
+    import_nodes(store, nodes, None, &mut summary);
+    Ok(summary)
+}
+
+pub fn parse(content: &str, format: BookmarkFormat) -> Result<Vec<ImportNode>, TransferError> {
+    match format {
+        BookmarkFormat::NetscapeHtml => Ok(parse_netscape(content)),
+        BookmarkFormat::ChromeJson => {
+            let json = serde_json::from_str(content).map_err(TransferError::InvalidJson)?;
+            Ok(parse_chrome(&json))
+        }
+        BookmarkFormat::FirefoxJson => {
+            let json = serde_json::from_str(content).map_err(TransferError::InvalidJson)?;
+            Ok(parse_firefox(&json))
+        }
+    }
+}
+
+fn import_nodes(
+    store: &mut BookmarkStore,
+    nodes: Vec<ImportNode>,
+    parent: Option<u64>,
+    summary: &mut ImportSummary,
+) {
+    for node in nodes {
+        match node {
+            ImportNode::Folder { name, children } => {
+                let existing = store.folders()
+                    .iter()
+                    .find(|f| f.parent_id == parent && f.name == name)
+                    .map(|f| f.id);
+                let folder_id = match existing {
+                    Some(id) => id,
+                    None => match store.add_folder(&name, parent) {
+                        Ok(folder) => {
+                            summary.folders_created += 1;
+                            folder.id
+                        }
+                        Err(_) => continue,
+                    },
+                };
+                import_nodes(store, children, Some(folder_id), summary);
+            }
+            ImportNode::Bookmark { title, url, tags, added } => {
+                if store.bookmarks().iter().any(|b| same_url(&b.url, &url)) {
+                    summary.duplicates += 1;
+                    continue;
+                }
+                let created_at = added.unwrap_or_else(now_secs);
+                if store.insert_bookmark(&url, &title, parent, tags, created_at).is_ok() {
+                    summary.added += 1;
+                }
+            }
+        }
+    }
+}
+
+/// URLs that differ only by a trailing slash are the same bookmark.
+fn same_url(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+fn parse_netscape(content: &str) -> Vec<ImportNode> {
+    // Each open <DL> is a container; the <H3> right before it names the folder.
+    let mut stack: Vec<(Option<String>, Vec<ImportNode>)> = vec![(None, Vec::new())];
+    let mut pending_folder: Option<String> = None;
+
+    for token in NETSCAPE_TOKEN.captures_iter(content) {
+        if let Some(slash) = token.get(1) {
+            if slash.as_str().is_empty() {
+                stack.push((pending_folder.take(), Vec::new()));
+            } else if stack.len() > 1 {
+                close_netscape_container(&mut stack);
+            }
+        } else if let (Some(attrs), Some(name)) = (token.get(2), token.get(3)) {
+            let attrs = html_attributes(attrs.as_str());
+            let name = decode_entities(name.as_str().trim());
+            pending_folder = Some(if attrs.iter().any(|(k, _)| k == "personal_toolbar_folder") {
+                TOOLBAR_FOLDER.to_string()
+            } else if attrs.iter().any(|(k, _)| k == "unfiled_bookmarks_folder") {
+                OTHER_FOLDER.to_string()
+            } else {
+                name
+            });
+        } else if let (Some(attrs), Some(title)) = (token.get(4), token.get(5)) {
+            let attrs = html_attributes(attrs.as_str());
+            let attr = |key: &str| attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+            let Some(url) = attr("href") else {
+                continue;
+            };
+            let node = ImportNode::Bookmark {
+                title: decode_entities(title.as_str().trim()),
+                url: url.to_string(),
+                tags: attr("tags").map(split_tags).unwrap_or_default(),
+                added: attr("add_date").and_then(|d| d.parse().ok()),
+            };
+            stack.last_mut().unwrap().1.push(node);
+        }
+    }
+
+    while stack.len() > 1 {
+        close_netscape_container(&mut stack);
+    }
+    stack.pop().map(|(_, nodes)| nodes).unwrap_or_default()
+}
+
+fn close_netscape_container(stack: &mut Vec<(Option<String>, Vec<ImportNode>)>) {
+    let (name, children) = stack.pop().unwrap();
+    let parent = &mut stack.last_mut().unwrap().1;
+    match name {
+        Some(name) => parent.push(ImportNode::Folder { name, children }),
+        None => parent.extend(children),
+    }
+}
+
+fn html_attributes(raw: &str) -> Vec<(String, String)> {
+    HTML_ATTRIBUTE
+        .captures_iter(raw)
+        .map(|c| (c[1].to_lowercase(), decode_entities(&c[2])))
+        .collect()
+}
+
+fn parse_chrome(json: &Value) -> Vec<ImportNode> {
+    let roots = [
+        ("bookmark_bar", TOOLBAR_FOLDER),
+        ("other", OTHER_FOLDER),
+        ("synced", MOBILE_FOLDER),
+    ];
+
+    roots
+        .iter()
+        .filter_map(|(key, folder)| {
+            let children = chrome_children(json.get("roots")?.get(key)?);
+            (!children.is_empty()).then(|| ImportNode::Folder {
+                name: folder.to_string(),
+                children,
+            })
+        })
+        .collect()
+}
+
+fn chrome_children(node: &Value) -> Vec<ImportNode> {
+    let children = node.get("children").and_then(Value::as_array);
+    children
+        .into_iter()
+        .flatten()
+        .filter_map(|child| {
+            let name = child.get("name").and_then(Value::as_str).unwrap_or_default().to_string();
+            match child.get("type").and_then(Value::as_str)? {
+                "folder" => Some(ImportNode::Folder { name, children: chrome_children(child) }),
+                "url" => Some(ImportNode::Bookmark {
+                    title: name,
+                    url: child.get("url").and_then(Value::as_str)?.to_string(),
+                    tags: Vec::new(),
+                    added: child.get("date_added")
+                        .and_then(Value::as_str)
+                        .and_then(|us| us.parse::<u64>().ok())
+                        .map(|us| (us / 1_000_000).saturating_sub(CHROME_EPOCH_OFFSET)),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn parse_firefox(json: &Value) -> Vec<ImportNode> {
+    firefox_children(json)
+}
+
+fn firefox_children(node: &Value) -> Vec<ImportNode> {
+    let children = node.get("children").and_then(Value::as_array);
+    children
+        .into_iter()
+        .flatten()
+        .filter_map(|child| {
+            let title = child.get("title").and_then(Value::as_str).unwrap_or_default();
+            match child.get("type").and_then(Value::as_str)? {
+                "text/x-moz-place-container" => {
+                    let name = match child.get("root").and_then(Value::as_str) {
+                        Some("toolbarFolder") => TOOLBAR_FOLDER,
+                        Some("bookmarksMenuFolder") => MENU_FOLDER,
+                        Some("unfiledBookmarksFolder") => OTHER_FOLDER,
+                        Some("mobileFolder") => MOBILE_FOLDER,
+                        _ => title,
+                    };
+                    let children = firefox_children(child);
+                    (!children.is_empty()).then(|| ImportNode::Folder {
+                        name: name.to_string(),
+                        children,
+                    })
+                }
+                "text/x-moz-place" => {
+                    let url = child.get("uri").and_then(Value::as_str)?;
+                    // `place:` URIs are saved searches, not pages.
+                    if url.starts_with("place:") {
+                        return None;
+                    }
+                    Some(ImportNode::Bookmark {
+                        title: title.to_string(),
+                        url: url.to_string(),
+                        tags: child.get("tags").and_then(Value::as_str).map(split_tags).unwrap_or_default(),
+                        added: child.get("dateAdded").and_then(Value::as_u64).map(|us| us / 1_000_000),
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn split_tags(raw: &str) -> Vec<String> {
+    raw.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect()
+}
+
+/// Writes the store as a Netscape bookmark file, which every browser can import.
+pub fn export_netscape(store: &BookmarkStore) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <!-- This is an automatically generated file.\n     It will be read and overwritten.\n     DO NOT EDIT! -->\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Bookmarks</TITLE>\n\
+         <H1>Bookmarks</H1>\n\
+         <DL><p>\n",
+    );
+    export_folder(store, None, 1, &mut out);
+    out.push_str("</DL><p>\n");
+    out
+}
+
+/// Writes the Netscape export next to the other browser data and returns its path.
+pub fn export_to_file(store: &BookmarkStore) -> io::Result<PathBuf> {
+    let path = storage::data_path(EXPORT_FILE);
+    std::fs::write(&path, export_netscape(store))?;
+    Ok(path)
+}
+
+fn export_folder(store: &BookmarkStore, folder_id: Option<u64>, depth: usize, out: &mut String) {
+    let indent = "    ".repeat(depth);
+
+    for folder in store.folders().iter().filter(|f| f.parent_id == folder_id) {
+        let toolbar = if folder.parent_id.is_none() && folder.name == TOOLBAR_FOLDER {
+            " PERSONAL_TOOLBAR_FOLDER=\"true\""
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "{}<DT><H3 ADD_DATE=\"{}\"{}>{}</H3>\n{}<DL><p>\n",
+            indent, folder.created_at, toolbar, encode_entities(&folder.name), indent
+        ));
+        export_folder(store, Some(folder.id), depth + 1, out);
+        out.push_str(&format!("{}</DL><p>\n", indent));
+    }
+
+    for bookmark in store.bookmarks().iter().filter(|b| b.folder_id == folder_id) {
+        let mut attrs = format!(
+            "HREF=\"{}\" ADD_DATE=\"{}\"",
+            encode_entities(&bookmark.url),
+            bookmark.created_at
+        );
+        if let Some(visited) = bookmark.last_visited {
+            attrs.push_str(&format!(" LAST_VISIT=\"{}\"", visited));
+        }
+        if !bookmark.tags.is_empty() {
+            attrs.push_str(&format!(" TAGS=\"{}\"", encode_entities(&bookmark.tags.join(","))));
+        }
+        out.push_str(&format!("{}<DT><A {}>{}</A>\n", indent, attrs, encode_entities(&bookmark.title)));
+    }
+}
+
+fn encode_entities(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub(crate) fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(title: &str, url: &str, tags: &[&str], added: Option<u64>) -> ImportNode {
+        ImportNode::Bookmark {
+            title: title.to_string(),
+            url: url.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            added,
+        }
+    }
+
+    fn folder(name: &str, children: Vec<ImportNode>) -> ImportNode {
+        ImportNode::Folder { name: name.to_string(), children }
+    }
+
+    const NETSCAPE: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><H3 ADD_DATE="1" PERSONAL_TOOLBAR_FOLDER="true">Toolbar</H3>
+    <DL><p>
+        <DT><A HREF="https://rust-lang.org/" ADD_DATE="1700000000" TAGS="rust, lang">Rust &amp; Cargo</A>
+        <DT><H3>Docs</H3>
+        <DL><p>
+            <DT><A HREF="https://docs.rs/">docs.rs</A>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="https://example.com/">Example</A>
+</DL><p>"#;
+
+    const CHROME: &str = r#"{
+        "roots": {
+            "bookmark_bar": { "type": "folder", "children": [
+                { "type": "url", "name": "Rust", "url": "https://rust-lang.org/", "date_added": "13344473600000000" },
+                { "type": "folder", "name": "Docs", "children": [
+                    { "type": "url", "name": "docs.rs", "url": "https://docs.rs/" }
+                ] }
+            ] },
+            "other": { "type": "folder", "children": [] },
+            "synced": { "type": "folder", "children": [
+                { "type": "url", "name": "Example", "url": "https://example.com/" }
+            ] }
+        }
+    }"#;
+
+    const FIREFOX: &str = r#"{
+        "type": "text/x-moz-place-container", "root": "placesRoot", "children": [
+            { "type": "text/x-moz-place-container", "root": "toolbarFolder", "title": "toolbar", "children": [
+                { "type": "text/x-moz-place", "title": "Rust", "uri": "https://rust-lang.org/",
+                  "dateAdded": 1700000000000000, "tags": "rust,lang" },
+                { "type": "text/x-moz-place", "title": "Recent", "uri": "place:sort=8" }
+            ] },
+            { "type": "text/x-moz-place-container", "root": "unfiledBookmarksFolder", "title": "unfiled", "children": [] },
+            { "type": "text/x-moz-place-container", "root": "mobileFolder", "title": "mobile", "children": [
+                { "type": "text/x-moz-place", "title": "Example", "uri": "https://example.com/" }
+            ] }
+        ]
+    }"#;
+
+    #[test]
+    fn detects_formats() {
+        let cases = [
+            (NETSCAPE, Some(BookmarkFormat::NetscapeHtml)),
+            (CHROME, Some(BookmarkFormat::ChromeJson)),
+            (FIREFOX, Some(BookmarkFormat::FirefoxJson)),
+            ("  <html></html>", Some(BookmarkFormat::NetscapeHtml)),
+            (r#"{"bookmarks": []}"#, None),
+            ("not bookmarks", None),
+            ("", None),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(BookmarkFormat::detect(content), expected, "{:?}", content);
+        }
+    }
+
+    #[test]
+    fn parses_netscape() {
+        let nodes = parse(NETSCAPE, BookmarkFormat::NetscapeHtml).unwrap();
+        assert_eq!(nodes, vec![
+            folder(TOOLBAR_FOLDER, vec![
+                bookmark("Rust & Cargo", "https://rust-lang.org/", &["rust", "lang"], Some(1_700_000_000)),
+                folder("Docs", vec![bookmark("docs.rs", "https://docs.rs/", &[], None)]),
+            ]),
+            bookmark("Example", "https://example.com/", &[], None),
+        ]);
+    }
+
+    #[test]
+    fn parses_chrome() {
+        let nodes = parse(CHROME, BookmarkFormat::ChromeJson).unwrap();
+        assert_eq!(nodes, vec![
+            folder(TOOLBAR_FOLDER, vec![
+                bookmark("Rust", "https://rust-lang.org/", &[], Some(1_700_000_000)),
+                folder("Docs", vec![bookmark("docs.rs", "https://docs.rs/", &[], None)]),
+            ]),
+            folder(MOBILE_FOLDER, vec![bookmark("Example", "https://example.com/", &[], None)]),
+        ]);
+    }
+
+    #[test]
+    fn parses_firefox() {
+        let nodes = parse(FIREFOX, BookmarkFormat::FirefoxJson).unwrap();
+        assert_eq!(nodes, vec![
+            folder(TOOLBAR_FOLDER, vec![
+                bookmark("Rust", "https://rust-lang.org/", &["rust", "lang"], Some(1_700_000_000)),
+            ]),
+            folder(MOBILE_FOLDER, vec![bookmark("Example", "https://example.com/", &[], None)]),
+        ]);
+    }
+
+    #[test]
+    fn rejects_broken_json() {
+        assert!(matches!(parse("{", BookmarkFormat::ChromeJson), Err(TransferError::InvalidJson(_))));
+        assert!(matches!(import(&mut BookmarkStore::default(), "plain text"), Err(TransferError::UnknownFormat)));
+    }
+
+    #[test]
+    fn import_merges_folders_and_skips_duplicates() {
+        let mut store = BookmarkStore::default();
+        let first = import(&mut store, CHROME).unwrap();
+        assert_eq!((first.added, first.duplicates, first.folders_created), (3, 0, 3));
+
+        // The same bookmarks from another browser, one without its trailing slash.
+        let second = import(&mut store, &FIREFOX.replace("https://example.com/", "https://example.com")).unwrap();
+        assert_eq!((second.added, second.duplicates, second.folders_created), (0, 2, 0));
+        assert_eq!(store.bookmarks().len(), 3);
+        assert_eq!(store.folders().len(), 3);
+    }
+
+    #[test]
+    fn round_trips() {
+        for (content, format) in [
+            (NETSCAPE, BookmarkFormat::NetscapeHtml),
+            (CHROME, BookmarkFormat::ChromeJson),
+            (FIREFOX, BookmarkFormat::FirefoxJson),
+        ] {
+            let mut store = BookmarkStore::default();
+            import(&mut store, content).unwrap();
+            let exported = export_netscape(&store);
+
+            let mut reimported = BookmarkStore::default();
+            let summary = import(&mut reimported, &exported).unwrap();
+            assert_eq!(summary.format, Some(BookmarkFormat::NetscapeHtml));
+            assert_eq!(summary.added, store.bookmarks().len(), "{:?}", format);
+            assert_eq!(summary.folders_created, store.folders().len(), "{:?}", format);
+            // The export lists folders before loose bookmarks, so compare by URL.
+            for before in store.bookmarks() {
+                let after = reimported.find_by_url(&before.url).unwrap();
+                assert_eq!(
+                    (&before.title, &before.tags, before.created_at),
+                    (&after.title, &after.tags, after.created_at),
+                    "{:?}",
+                    format
+                );
+            }
+            assert_eq!(export_netscape(&reimported), exported, "{:?}", format);
+        }
+    }
+}
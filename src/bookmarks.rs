@@ -0,0 +1,334 @@
+// bookmarks.rs - Bookmark folders and entries persisted in browser_data/bookmarks.json
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use crate::storage;
+use crate::tab_history::now_secs;
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Bookmark {
+    pub id: u64,
+    pub title: String,
+    pub url: String,
+    /// `None` means the top level.
+    pub folder_id: Option<u64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub last_visited: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BookmarkFolder {
+    pub id: u64,
+    pub name: String,
+    pub parent_id: Option<u64>,
+    pub created_at: u64,
+}
+
+/// Optional field changes for `BookmarkStore::update_bookmark`.
+#[derive(Deserialize, Default, Debug)]
+pub struct BookmarkUpdate {
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BookmarkError {
+    NotFound(u64),
+    FolderNotFound(u64),
+    EmptyUrl,
+    FolderCycle,
+}
+
+impl fmt::Display for BookmarkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookmarkError::NotFound(id) => write!(f, "No bookmark with id {}", id),
+            BookmarkError::FolderNotFound(id) => write!(f, "No folder with id {}", id),
+            BookmarkError::EmptyUrl => write!(f, "Bookmark URL cannot be empty"),
+            BookmarkError::FolderCycle => write!(f, "A folder cannot be moved into itself"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BookmarkStore {
+    next_id: u64,
+    folders: Vec<BookmarkFolder>,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl BookmarkStore {
+    pub fn load() -> Self {
+        match storage::load_json(BOOKMARKS_FILE) {
+            Ok(store) => store.unwrap_or_default(),
+            Err(e) => {
+                log::warn!("Ignoring unreadable bookmarks: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save_json(BOOKMARKS_FILE, self) {
+            log::warn!("Failed to save bookmarks: {}", e);
+        }
+    }
+
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    pub fn folders(&self) -> &[BookmarkFolder] {
+        &self.folders
+    }
+
+    pub fn find_by_url(&self, url: &str) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|b| b.url == url)
+    }
+
+    pub fn add_bookmark(
+        &mut self,
+        url: &str,
+        title: &str,
+        folder_id: Option<u64>,
+        tags: Vec<String>,
+    ) -> Result<&Bookmark, BookmarkError> {
+        self.insert_bookmark(url, title, folder_id, tags, now_secs())
+    }
+
+    /// Like `add_bookmark`, but keeps an existing creation time (used by imports).
+    pub fn insert_bookmark(
+        &mut self,
+        url: &str,
+        title: &str,
+        folder_id: Option<u64>,
+        tags: Vec<String>,
+        created_at: u64,
+    ) -> Result<&Bookmark, BookmarkError> {
+        let url = url.trim();
+        if url.is_empty() {
+            return Err(BookmarkError::EmptyUrl);
+        }
+        self.check_folder(folder_id)?;
+
+        let title = match title.trim() {
+            "" => url,
+            t => t,
+        };
+        let bookmark = Bookmark {
+            id: self.allocate_id(),
+            title: title.to_string(),
+            url: url.to_string(),
+            folder_id,
+            tags: normalize_tags(tags),
+            created_at,
+            last_visited: None,
+        };
+        self.bookmarks.push(bookmark);
+        Ok(self.bookmarks.last().unwrap())
+    }
+
+    pub fn update_bookmark(&mut self, id: u64, update: BookmarkUpdate) -> Result<(), BookmarkError> {
+        if matches!(&update.url, Some(url) if url.trim().is_empty()) {
+            return Err(BookmarkError::EmptyUrl);
+        }
+        let bookmark = self.bookmark_mut(id)?;
+        if let Some(title) = update.title {
+            bookmark.title = title.trim().to_string();
+        }
+        if let Some(url) = update.url {
+            bookmark.url = url.trim().to_string();
+        }
+        if let Some(tags) = update.tags {
+            bookmark.tags = normalize_tags(tags);
+        }
+        Ok(())
+    }
+
+    pub fn move_bookmark(&mut self, id: u64, folder_id: Option<u64>) -> Result<(), BookmarkError> {
+        self.check_folder(folder_id)?;
+        self.bookmark_mut(id)?.folder_id = folder_id;
+        Ok(())
+    }
+
+    pub fn remove_bookmark(&mut self, id: u64) -> Result<(), BookmarkError> {
+        let before = self.bookmarks.len();
+        self.bookmarks.retain(|b| b.id != id);
+        if self.bookmarks.len() == before {
+            return Err(BookmarkError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    pub fn add_folder(&mut self, name: &str, parent_id: Option<u64>) -> Result<&BookmarkFolder, BookmarkError> {
+        self.check_folder(parent_id)?;
+        let folder = BookmarkFolder {
+            id: self.allocate_id(),
+            name: name.trim().to_string(),
+            parent_id,
+            created_at: now_secs(),
+        };
+        self.folders.push(folder);
+        Ok(self.folders.last().unwrap())
+    }
+
+    pub fn rename_folder(&mut self, id: u64, name: &str) -> Result<(), BookmarkError> {
+        self.folder_mut(id)?.name = name.trim().to_string();
+        Ok(())
+    }
+
+    pub fn move_folder(&mut self, id: u64, parent_id: Option<u64>) -> Result<(), BookmarkError> {
+        self.check_folder(parent_id)?;
+        // Walk up from the new parent; meeting `id` means we'd create a cycle.
+        let mut cursor = parent_id;
+        while let Some(current) = cursor {
+            if current == id {
+                return Err(BookmarkError::FolderCycle);
+            }
+            cursor = self.folders.iter().find(|f| f.id == current).and_then(|f| f.parent_id);
+        }
+        self.folder_mut(id)?.parent_id = parent_id;
+        Ok(())
+    }
+
+    /// Removes a folder together with everything nested inside it.
+    pub fn remove_folder(&mut self, id: u64) -> Result<(), BookmarkError> {
+        self.folder_mut(id)?;
+        let mut doomed = vec![id];
+        let mut i = 0;
+        while i < doomed.len() {
+            let parent = doomed[i];
+            doomed.extend(self.folders.iter().filter(|f| f.parent_id == Some(parent)).map(|f| f.id));
+            i += 1;
+        }
+        self.folders.retain(|f| !doomed.contains(&f.id));
+        self.bookmarks.retain(|b| !b.folder_id.is_some_and(|f| doomed.contains(&f)));
+        Ok(())
+    }
+
+    /// Stamps the visit time on bookmarks for `url`. Returns whether any matched.
+    pub fn mark_visited(&mut self, url: &str) -> bool {
+        let now = now_secs();
+        let mut matched = false;
+        for bookmark in self.bookmarks.iter_mut().filter(|b| b.url == url) {
+            bookmark.last_visited = Some(now);
+            matched = true;
+        }
+        matched
+    }
+
+    fn allocate_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn check_folder(&self, folder_id: Option<u64>) -> Result<(), BookmarkError> {
+        match folder_id {
+            Some(id) if !self.folders.iter().any(|f| f.id == id) => Err(BookmarkError::FolderNotFound(id)),
+            _ => Ok(()),
+        }
+    }
+
+    fn bookmark_mut(&mut self, id: u64) -> Result<&mut Bookmark, BookmarkError> {
+        self.bookmarks.iter_mut().find(|b| b.id == id).ok_or(BookmarkError::NotFound(id))
+    }
+
+    fn folder_mut(&mut self, id: u64) -> Result<&mut BookmarkFolder, BookmarkError> {
+        self.folders.iter_mut().find(|f| f.id == id).ok_or(BookmarkError::FolderNotFound(id))
+    }
+}
+
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .into_iter()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Folders a > b > c, plus d at the top level.
+    fn tree() -> (BookmarkStore, [u64; 4]) {
+        let mut store = BookmarkStore::default();
+        let a = store.add_folder("a", None).unwrap().id;
+        let b = store.add_folder("b", Some(a)).unwrap().id;
+        let c = store.add_folder("c", Some(b)).unwrap().id;
+        let d = store.add_folder("d", None).unwrap().id;
+        (store, [a, b, c, d])
+    }
+
+    #[test]
+    fn move_folder_rejects_cycles() {
+        let (store, [a, b, c, d]) = tree();
+        let cases = [
+            (a, Some(a), Err(BookmarkError::FolderCycle)),
+            (a, Some(b), Err(BookmarkError::FolderCycle)),
+            (a, Some(c), Err(BookmarkError::FolderCycle)),
+            (b, Some(c), Err(BookmarkError::FolderCycle)),
+            (c, Some(a), Ok(())),
+            (a, Some(d), Ok(())),
+            (b, None, Ok(())),
+            (a, Some(99), Err(BookmarkError::FolderNotFound(99))),
+            (99, Some(a), Err(BookmarkError::FolderNotFound(99))),
+        ];
+        for (id, parent_id, expected) in cases {
+            let mut store = store.clone();
+            assert_eq!(store.move_folder(id, parent_id), expected, "{} into {:?}", id, parent_id);
+            if expected.is_ok() {
+                let moved = store.folders().iter().find(|f| f.id == id).unwrap();
+                assert_eq!(moved.parent_id, parent_id);
+            }
+        }
+    }
+
+    #[test]
+    fn remove_folder_takes_nested_folders_and_bookmarks() {
+        let (mut store, [a, b, c, d]) = tree();
+        let bookmarks = [
+            ("https://a/", Some(a)),
+            ("https://c/", Some(c)),
+            ("https://d/", Some(d)),
+            ("https://top/", None),
+        ];
+        for (url, folder_id) in bookmarks {
+            store.add_bookmark(url, "", folder_id, Vec::new()).unwrap();
+        }
+
+        store.remove_folder(b).unwrap();
+        let folders: Vec<u64> = store.folders().iter().map(|f| f.id).collect();
+        let urls: Vec<&str> = store.bookmarks().iter().map(|b| b.url.as_str()).collect();
+        assert_eq!(folders, vec![a, d]);
+        assert_eq!(urls, vec!["https://a/", "https://d/", "https://top/"]);
+
+        store.remove_folder(a).unwrap();
+        let urls: Vec<&str> = store.bookmarks().iter().map(|b| b.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://d/", "https://top/"]);
+        assert_eq!(store.remove_folder(a), Err(BookmarkError::FolderNotFound(a)));
+    }
+
+    #[test]
+    fn normalizes_tags() {
+        let cases: [(&[&str], &[&str]); 5] = [
+            (&[], &[]),
+            (&["Rust", "rust", " RUST "], &["rust"]),
+            (&["b", "a", "c"], &["a", "b", "c"]),
+            (&["", "  ", "news"], &["news"]),
+            (&["Read Later", "read later"], &["read later"]),
+        ];
+        for (tags, expected) in cases {
+            let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+            assert_eq!(normalize_tags(tags.clone()), expected, "{:?}", tags);
+        }
+    }
+}
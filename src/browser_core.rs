@@ -1,9 +1,31 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
 use url::Url;
+use crate::bangs::BangTable;
+use crate::bookmarks::BookmarkStore;
+use crate::browsing_history::{BrowsingHistory, Transition, Visit};
+use crate::content_index::{ContentHit, ContentIndex};
+use crate::memory_history::MemoryHistory;
+use crate::navigation::{self, NavigationTarget};
+use crate::opensearch::{self, DiscoveredEngine, OpenSearchError};
+use crate::process_memory::{self, MemoryBreakdown};
+use crate::redirects::RedirectRules;
+use crate::search_engines::{SearchEngine, SearchEngineError, SearchEngineRegistry};
+use crate::session::{self, SessionState};
+use crate::settings::Settings;
+use crate::tab_history::{now_secs, HistoryEntry, TabHistory};
+use crate::url_cleaner::UrlCleaner;
 
-#[derive(Clone, Debug)]
+/// A tab gets at most one page indexed per this many seconds, so a page
+/// can't flood the content index by reloading or navigating itself.
+const MIN_INDEX_INTERVAL_SECS: u64 = 10;
+/// A site can offer a couple of engines (say, docs and forum search); more
+/// than this from one origin is a page spamming the settings page.
+const MAX_OFFERS_PER_ORIGIN: usize = 3;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TabData {
     pub id: usize,
     pub title: String,
@@ -11,6 +33,26 @@ pub struct TabData {
     pub display_url: String,
     pub can_go_back: bool,
     pub can_go_forward: bool,
+    pub history: TabHistory,
+    /// Unloaded to save memory; the shell reloads it when it becomes active.
+    #[serde(default)]
+    pub discarded: bool,
+    /// Unix seconds when the tab was last the active one, for the idle timeout.
+    #[serde(default)]
+    pub last_active: u64,
+}
+
+impl TabData {
+    /// Refreshes the visible fields from the current history entry.
+    fn sync_from_history(&mut self) {
+        if let Some(entry) = self.history.current() {
+            self.url = entry.url.clone();
+            self.display_url = entry.display_url.clone();
+            self.title = entry.title.clone();
+        }
+        self.can_go_back = self.history.can_go_back();
+        self.can_go_forward = self.history.can_go_forward();
+    }
 }
 
 pub struct BrowserCore {
@@ -21,6 +63,22 @@ pub struct BrowserCore {
     ultimate_boost: bool,
     flash_handler: FlashHandler,
     memory_limit_mb: usize,
+    memory_history: MemoryHistory,
+    settings: Settings,
+    /// Session left behind by a run that did not shut down cleanly.
+    recovered_session: Option<SessionState>,
+    bookmarks: BookmarkStore,
+    browsing_history: BrowsingHistory,
+    content_index: ContentIndex,
+    /// OpenSearch engines offered by pages this run, not yet installed.
+    discovered_engines: Vec<DiscoveredEngine>,
+    dismissed_descriptors: Vec<String>,
+    bangs: BangTable,
+    url_cleaner: UrlCleaner,
+    /// Hosts allowed over http until exit ("continue over HTTP once").
+    http_allowed_hosts: HashSet<String>,
+    /// When each tab last had a page indexed, for `MIN_INDEX_INTERVAL_SECS`.
+    last_indexed: HashMap<usize, u64>,
 }
 
 pub struct FlashHandler {
@@ -117,77 +175,679 @@ impl BrowserCore {
             ultimate_boost: false,
             flash_handler: FlashHandler::new(),
             memory_limit_mb: 512,
+            memory_history: MemoryHistory::default(),
+            settings: Settings::default(),
+            recovered_session: None,
+            bookmarks: BookmarkStore::default(),
+            browsing_history: BrowsingHistory::default(),
+            content_index: ContentIndex::default(),
+            discovered_engines: Vec::new(),
+            dismissed_descriptors: Vec::new(),
+            bangs: BangTable::default(),
+            url_cleaner: UrlCleaner::default(),
+            http_allowed_hosts: HashSet::new(),
+            last_indexed: HashMap::new(),
+        }
+    }
+    
+    pub fn session_state(&self) -> SessionState {
+        SessionState::new(
+            self.get_tabs().into_iter().cloned().collect(),
+            self.active_tab_id,
+            self.booster_mode,
+            self.ultimate_boost,
+            self.flash_handler.enabled,
+        )
+    }
+    
+    /// Replaces all tabs and modes with a saved session. Only the active tab
+    /// is loaded; the others stay discarded until they are selected.
+    pub fn restore_session(&mut self, session: SessionState) {
+        self.tabs = session.tabs
+            .into_iter()
+            .map(|mut t| {
+                t.discarded = true;
+                (t.id, t)
+            })
+            .collect();
+        self.tab_counter = self.tabs.keys().max().map_or(0, |id| id + 1);
+        self.active_tab_id = None;
+        let active = session.active_tab_id
+            .filter(|id| self.tabs.contains_key(id))
+            .or_else(|| self.get_tabs().first().map(|t| t.id));
+        if let Some(tab_id) = active {
+            self.activate(tab_id);
+        }
+        self.booster_mode = session.booster_mode;
+        self.ultimate_boost = session.ultimate_boost;
+        self.flash_handler.enabled = session.flash_enabled;
+    }
+    
+    pub fn set_recovered_session(&mut self, session: SessionState) {
+        self.recovered_session = Some(session);
+    }
+    
+    pub fn get_recovered_tabs(&self) -> Vec<TabData> {
+        self.recovered_session
+            .as_ref()
+            .map(|s| s.tabs.clone())
+            .unwrap_or_default()
+    }
+    
+    /// Reopens the chosen recovered tabs (by their old ids) as new tabs,
+    /// keeping their history. The first becomes active and the rest load
+    /// when selected. Returns how many were restored.
+    pub fn restore_recovered_tabs(&mut self, tab_ids: &[usize]) -> usize {
+        let Some(session) = self.recovered_session.as_mut() else {
+            return 0;
+        };
+        
+        let (chosen, remaining): (Vec<TabData>, Vec<TabData>) = session.tabs
+            .drain(..)
+            .partition(|t| tab_ids.contains(&t.id));
+        session.tabs = remaining;
+        session::save_recovered(Some(session));
+        
+        let count = chosen.len();
+        let mut first_restored = None;
+        for mut tab in chosen {
+            tab.id = self.tab_counter;
+            tab.discarded = true;
+            self.tab_counter += 1;
+            first_restored.get_or_insert(tab.id);
+            self.tabs.insert(tab.id, tab);
+        }
+        if let Some(tab_id) = first_restored {
+            self.activate(tab_id);
+        }
+        
+        count
+    }
+    
+    pub fn discard_recovered_session(&mut self) {
+        self.recovered_session = None;
+        session::save_recovered(None);
+    }
+    
+    pub fn get_bookmarks(&self) -> &BookmarkStore {
+        &self.bookmarks
+    }
+    
+    pub fn set_bookmarks(&mut self, bookmarks: BookmarkStore) {
+        self.bookmarks = bookmarks;
+    }
+    
+    /// Runs a change against the bookmark store and persists it if it succeeded.
+    pub fn edit_bookmarks<T, E>(
+        &mut self,
+        edit: impl FnOnce(&mut BookmarkStore) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let result = edit(&mut self.bookmarks)?;
+        self.bookmarks.save();
+        Ok(result)
+    }
+    
+    pub fn set_browsing_history(&mut self, history: BrowsingHistory) {
+        self.browsing_history = history;
+    }
+    
+    pub fn get_browsing_history(&self) -> &BrowsingHistory {
+        &self.browsing_history
+    }
+    
+    pub fn search_history(&self, query: &str, limit: usize) -> Vec<Visit> {
+        self.browsing_history.search(query, limit)
+    }
+    
+    /// Deleting the last visit to a URL also drops its indexed page text.
+    pub fn delete_history_visit(&mut self, id: u64) -> bool {
+        let url = self.browsing_history.visits().iter().find(|v| v.id == id).map(|v| v.url.clone());
+        if !self.browsing_history.delete_visit(id) {
+            return false;
+        }
+        if let Some(url) = url {
+            if !self.browsing_history.visits().iter().any(|v| v.url == url) {
+                self.content_index.remove_url(&url);
+            }
+        }
+        true
+    }
+    
+    pub fn delete_history_range(&mut self, from: u64, to: u64) -> usize {
+        self.content_index.remove_range(from, to);
+        self.browsing_history.delete_range(from, to)
+    }
+    
+    pub fn set_content_index(&mut self, index: ContentIndex) {
+        self.content_index = index;
+    }
+    
+    /// Stores text extracted from the page in `tab_id` under the tab's URL,
+    /// unless indexing is off or the page is internal. The text is dropped if
+    /// it came from a document on another origin than the tab's URL (the tab
+    /// moved on), or if the tab already had a page indexed too recently.
+    pub fn index_page_content(&mut self, tab_id: usize, origin: &str, text: &str) {
+        let Some(tab) = self.tab_on_origin(tab_id, origin) else {
+            return;
+        };
+        if !self.settings.index_page_content || !BrowsingHistory::is_recordable(&tab.url) {
+            return;
+        }
+        let now = now_secs();
+        let too_soon = self.last_indexed
+            .get(&tab_id)
+            .is_some_and(|&at| now.saturating_sub(at) < MIN_INDEX_INTERVAL_SECS);
+        if too_soon {
+            return;
+        }
+        let (url, title) = (tab.url.clone(), tab.title.clone());
+        self.last_indexed.insert(tab_id, now);
+        self.content_index.index_page(&url, &title, text);
+    }
+    
+    /// The tab, if its current URL is on `origin` (as a message event
+    /// reports it). Anything a page sends about itself is checked with this.
+    fn tab_on_origin(&self, tab_id: usize, origin: &str) -> Option<&TabData> {
+        self.tabs
+            .get(&tab_id)
+            .filter(|tab| Url::parse(&tab.url).is_ok_and(|url| url.origin().ascii_serialization() == origin))
+    }
+    
+    pub fn search_content(&self, query: &str, since: Option<u64>, limit: usize) -> Vec<ContentHit> {
+        self.content_index.search(query, since, limit)
+    }
+    
+    pub fn clear_content_index(&mut self) {
+        self.content_index.clear();
+    }
+    
+    pub fn get_settings(&self) -> &Settings {
+        &self.settings
+    }
+    
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+    
+    pub fn set_restore_session(&mut self, enabled: bool) {
+        self.settings.restore_session = enabled;
+        self.settings.save();
+    }
+    
+    /// Runs a change against the redirect rules and saves settings if it succeeded.
+    pub fn edit_redirects<T, E>(
+        &mut self,
+        edit: impl FnOnce(&mut RedirectRules) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let result = edit(&mut self.settings.redirects)?;
+        self.settings.save();
+        Ok(result)
+    }
+    
+    /// Runs a change against the search engine registry and saves settings if it succeeded.
+    pub fn edit_search_engines<T, E>(
+        &mut self,
+        edit: impl FnOnce(&mut SearchEngineRegistry) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let result = edit(&mut self.settings.search_engines)?;
+        self.settings.save();
+        Ok(result)
+    }
+    
+    fn is_known_descriptor(&self, descriptor_url: &str) -> bool {
+        self.settings.search_engines.has_descriptor(descriptor_url)
+            || self.dismissed_descriptors.iter().any(|d| d == descriptor_url)
+            || self.discovered_engines.iter().any(|e| e.descriptor_url == descriptor_url)
+    }
+    
+    /// Records an engine advertised by the page in `tab_id`, unless it's
+    /// installed, already offered or dismissed. The page fetched the
+    /// descriptor itself, so the message must come from the tab's origin and
+    /// the descriptor must live on that origin too.
+    pub fn discover_search_engine(
+        &mut self,
+        tab_id: usize,
+        origin: &str,
+        descriptor_url: &str,
+        xml: &str,
+    ) -> Result<(), OpenSearchError> {
+        let Some(page_url) = self.tab_on_origin(tab_id, origin).map(|tab| tab.url.clone()) else {
+            return Err(OpenSearchError::NotFromPage);
+        };
+        let descriptor = Url::parse(descriptor_url)
+            .map_err(|_| OpenSearchError::InvalidUrl(descriptor_url.to_string()))?;
+        if descriptor.origin().ascii_serialization() != origin {
+            return Err(OpenSearchError::NotFromPage);
+        }
+        if self.settings.https_only && descriptor.scheme() == "http" {
+            return Err(OpenSearchError::InsecureDescriptor);
+        }
+        if self.is_known_descriptor(descriptor_url) {
+            return Ok(());
+        }
+        let offered = self.discovered_engines
+            .iter()
+            .filter(|e| Url::parse(&e.descriptor_url).is_ok_and(|u| u.origin().ascii_serialization() == origin))
+            .count();
+        if offered >= MAX_OFFERS_PER_ORIGIN {
+            return Err(OpenSearchError::TooManyOffers);
         }
+        let engine = opensearch::parse(xml, descriptor_url, &page_url, &self.settings.search_engines)?;
+        self.discovered_engines.push(engine);
+        Ok(())
+    }
+    
+    pub fn get_discovered_engines(&self) -> &[DiscoveredEngine] {
+        &self.discovered_engines
+    }
+    
+    /// Adds a discovered engine to the registry under `keyword` (or the suggested one).
+    pub fn install_discovered_engine(
+        &mut self,
+        descriptor_url: &str,
+        keyword: Option<&str>,
+    ) -> Result<(), SearchEngineError> {
+        let index = self.discovered_engines
+            .iter()
+            .position(|e| e.descriptor_url == descriptor_url)
+            .ok_or_else(|| SearchEngineError::NotDiscovered(descriptor_url.to_string()))?;
+        let discovered = self.discovered_engines[index].clone();
+        let keyword = keyword
+            .filter(|k| !k.trim().is_empty())
+            .unwrap_or(&discovered.suggested_keyword)
+            .to_string();
+        self.edit_search_engines(|r| r.add(discovered.into_engine(&keyword)))?;
+        self.discovered_engines.remove(index);
+        Ok(())
+    }
+    
+    pub fn dismiss_discovered_engine(&mut self, descriptor_url: &str) {
+        self.discovered_engines.retain(|e| e.descriptor_url != descriptor_url);
+        self.dismissed_descriptors.push(descriptor_url.to_string());
+    }
+    
+    /// The engine offered by the active tab's site, for the address bar's install button.
+    fn search_engine_offer(&self) -> Option<DiscoveredEngine> {
+        let host = |url: &str| Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string));
+        let active = host(&self.get_active_tab()?.url)?;
+        self.discovered_engines
+            .iter()
+            .find(|e| host(&e.page_url).as_ref() == Some(&active))
+            .cloned()
+    }
+    
+    pub fn get_bangs(&self) -> &BangTable {
+        &self.bangs
+    }
+    
+    pub fn set_bangs(&mut self, bangs: BangTable) {
+        self.bangs = bangs;
+    }
+    
+    /// Runs a change against the bang table and persists it if it succeeded.
+    pub fn edit_bangs<T, E>(
+        &mut self,
+        edit: impl FnOnce(&mut BangTable) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let result = edit(&mut self.bangs)?;
+        self.bangs.save();
+        Ok(result)
+    }
+    
+    pub fn set_index_page_content(&mut self, enabled: bool) {
+        self.settings.index_page_content = enabled;
+        self.settings.save();
+    }
+    
+    pub fn set_url_cleaner(&mut self, url_cleaner: UrlCleaner) {
+        self.url_cleaner = url_cleaner;
+    }
+    
+    pub fn set_clean_urls(&mut self, enabled: bool) {
+        self.settings.clean_urls = enabled;
+        self.settings.save();
     }
     
     pub fn create_tab(&mut self, url: &str) -> TabData {
         let tab_id = self.tab_counter;
         self.tab_counter += 1;
         
-        let (real_url, display_url) = self.process_url(url);
+        let location = self.locate(url);
+        let title = Self::get_title_from_url(&location.display_url);
         
         let tab = TabData {
             id: tab_id,
-            title: Self::get_title_from_url(&display_url),
-            url: real_url.clone(),
-            display_url,
+            history: TabHistory::new(location.history_entry(&title)),
+            title,
+            url: location.url,
+            display_url: location.display_url,
             can_go_back: false,
             can_go_forward: false,
+            discarded: false,
+            last_active: now_secs(),
         };
         
         self.tabs.insert(tab_id, tab.clone());
-        self.active_tab_id = Some(tab_id);
+        self.activate(tab_id);
+        self.record_visit(tab_id, &tab.url, &tab.title, Transition::Typed);
         
         tab
     }
     
     pub fn close_tab(&mut self, tab_id: usize) {
         self.tabs.remove(&tab_id);
+        self.last_indexed.remove(&tab_id);
         if self.active_tab_id == Some(tab_id) {
-            self.active_tab_id = self.tabs.keys().next().copied();
+            self.active_tab_id = None;
+            if let Some(next) = self.tabs.keys().next().copied() {
+                self.activate(next);
+            }
+        }
+    }
+    
+    pub fn switch_tab(&mut self, tab_id: usize) -> bool {
+        if !self.tabs.contains_key(&tab_id) {
+            return false;
         }
+        self.activate(tab_id);
+        true
     }
     
-    pub fn process_url(&self, input: &str) -> (String, String) {
-        if input.is_empty() {
-            return ("minimalist://newtab".to_string(), "New Tab".to_string());
+    /// Makes `tab_id` the active tab, bringing it back if it was discarded.
+    /// The tab being left counts as used until now.
+    fn activate(&mut self, tab_id: usize) {
+        let now = now_secs();
+        if let Some(previous) = self.active_tab_id.and_then(|id| self.tabs.get_mut(&id)) {
+            previous.last_active = now;
         }
+        if let Some(tab) = self.tabs.get_mut(&tab_id) {
+            tab.discarded = false;
+            tab.last_active = now;
+            self.active_tab_id = Some(tab_id);
+        }
+    }
+    
+    pub fn navigate(&mut self, tab_id: usize, input: &str) -> Option<TabData> {
+        let location = self.locate(input);
+        let title = Self::get_title_from_url(&location.display_url);
+        let tab = self.tabs.get_mut(&tab_id)?;
         
-        let trimmed = input.trim();
+        tab.history.push(location.history_entry(&title));
+        tab.sync_from_history();
+        let tab = tab.clone();
         
-        // Already a full URL
-        if trimmed.starts_with("http://") 
-            || trimmed.starts_with("https://")
-            || trimmed.starts_with("file://")
-            || trimmed.starts_with("minimalist://") {
-            return (trimmed.to_string(), trimmed.to_string());
+        self.record_visit(tab_id, &location.url, &title, Transition::Typed);
+        Some(tab)
+    }
+    
+    /// The shell couldn't reach the https version of an upgraded page: swap
+    /// the entry for the interstitial offering to continue over http.
+    pub fn https_upgrade_failed(&mut self, tab_id: usize, url: &str) -> Option<TabData> {
+        let tab = self.tabs.get_mut(&tab_id)?;
+        let entry = tab.history.current_mut()?;
+        if entry.url != url {
+            return None;
+        }
+        let http_url = entry.upgraded_from.clone()?;
+        let interstitial = format!("minimalist:///https-only?url={}", urlencoding::encode(&http_url));
+        *entry = HistoryEntry::new(&interstitial, &http_url, "HTTPS Not Available");
+        tab.sync_from_history();
+        Some(tab.clone())
+    }
+    
+    /// Loads `http_url` in the tab without upgrading, for this run or (`always`) for good.
+    /// Replaces the interstitial rather than stacking a new entry after it.
+    pub fn continue_over_http(&mut self, tab_id: usize, http_url: &str, always: bool) -> Option<TabData> {
+        let url = Url::parse(http_url).ok().filter(|u| u.scheme() == "http")?;
+        let host = url.host_str()?.to_string();
+        if always {
+            if !self.settings.http_exceptions.contains(&host) {
+                self.settings.http_exceptions.push(host);
+                self.settings.save();
+            }
+        } else {
+            self.http_allowed_hosts.insert(host);
         }
         
-        // Localhost or IP
-        if trimmed.starts_with("localhost") || trimmed.starts_with("127.0.0.1") {
-            let url = format!("http://{}", trimmed);
-            return (url.clone(), trimmed.to_string());
+        let url = String::from(url);
+        let title = Self::get_title_from_url(&url);
+        let tab = self.tabs.get_mut(&tab_id)?;
+        let entry = HistoryEntry::new(&url, &url, &title);
+        match tab.history.current_mut() {
+            Some(current) if current.url.starts_with("minimalist:///https-only") => *current = entry,
+            _ => tab.history.push(entry),
         }
+        tab.sync_from_history();
+        let tab = tab.clone();
         
-        // Check if it looks like a domain
-        if self.looks_like_domain(trimmed) {
-            let url = format!("https://{}", trimmed);
-            return (url, trimmed.to_string());
+        self.record_visit(tab_id, &url, &title, Transition::Typed);
+        Some(tab)
+    }
+    
+    pub fn set_https_only(&mut self, enabled: bool) {
+        self.settings.https_only = enabled;
+        self.settings.save();
+    }
+    
+    pub fn set_discard_after_mins(&mut self, minutes: u64) {
+        self.settings.discard_after_mins = minutes;
+        self.settings.save();
+    }
+    
+    pub fn set_auto_ultimate_boost(&mut self, enabled: bool) {
+        self.settings.auto_ultimate_boost = enabled;
+        self.settings.save();
+    }
+    
+    pub fn remove_http_exception(&mut self, host: &str) {
+        self.settings.http_exceptions.retain(|h| h != host);
+        self.http_allowed_hosts.remove(host);
+        self.settings.save();
+    }
+    
+    /// Records what the shell actually loaded. A URL that differs from the
+    /// current entry commits a new entry if the user followed a link, and
+    /// otherwise (a redirect, or the page moving itself on) replaces the
+    /// current one so Back doesn't land on a page that bounces forward again.
+    pub fn update_tab_from_page(&mut self, tab_id: usize, url: &str, title: Option<&str>, user_initiated: bool) {
+        let Some(tab) = self.tabs.get_mut(&tab_id) else {
+            return;
+        };
+        
+        let title = match title.map(str::trim) {
+            Some(t) if !t.is_empty() => t.to_string(),
+            _ => Self::get_title_from_url(url),
+        };
+        
+        if tab.url != url {
+            let entry = HistoryEntry::new(url, url, &title);
+            let transition = if user_initiated {
+                tab.history.push(entry);
+                Transition::Link
+            } else {
+                tab.history.replace(entry);
+                Transition::Redirect
+            };
+            tab.sync_from_history();
+            self.record_visit(tab_id, url, &title, transition);
+        } else {
+            if let Some(entry) = tab.history.current_mut() {
+                entry.title = title.clone();
+            }
+            tab.sync_from_history();
+            self.browsing_history.update_title(tab_id, url, &title);
         }
+    }
+    
+    /// Traverses the tab's history by `delta` entries, like `history.go(n)`.
+    pub fn go(&mut self, tab_id: usize, delta: isize) -> Option<TabData> {
+        let tab = self.tabs.get_mut(&tab_id)?;
+        tab.history.go(delta)?;
+        tab.sync_from_history();
+        let tab = tab.clone();
         
-        // Default to Google search
-        let encoded = urlencoding::encode(trimmed);
-        let search = format!("https://www.google.com/search?q={}", encoded);
-        (search, trimmed.to_string())
-    }
-    
-    fn looks_like_domain(&self, input: &str) -> bool {
-        input.contains('.') 
-            && !input.contains(' ') 
-            && !input.contains('\n')
-            && input.chars().all(|c| {
-                c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == ':' || c == '/'
-            })
+        self.record_visit(tab_id, &tab.url, &tab.title, Transition::BackForward);
+        Some(tab)
+    }
+    
+    pub fn reload(&mut self, tab_id: usize) -> Option<TabData> {
+        let tab = self.tabs.get(&tab_id)?.clone();
+        self.record_visit(tab_id, &tab.url, &tab.title, Transition::Reload);
+        Some(tab)
+    }
+    
+    fn record_visit(&mut self, tab_id: usize, url: &str, title: &str, transition: Transition) {
+        if self.bookmarks.mark_visited(url) {
+            self.bookmarks.save();
+        }
+        self.browsing_history.record(url, title, transition, tab_id);
+    }
+    
+    pub fn go_back(&mut self, tab_id: usize) -> Option<TabData> {
+        self.go(tab_id, -1)
+    }
+    
+    pub fn go_forward(&mut self, tab_id: usize) -> Option<TabData> {
+        self.go(tab_id, 1)
+    }
+    
+    pub fn set_scroll_position(&mut self, tab_id: usize, x: f64, y: f64) {
+        if let Some(entry) = self.tabs.get_mut(&tab_id).and_then(|t| t.history.current_mut()) {
+            entry.scroll_x = x;
+            entry.scroll_y = y;
+        }
+    }
+    
+    /// Classifies address-bar input, resolving bangs and engine keywords first.
+    /// Web URLs come back cleaned of tracking parameters (when enabled) and
+    /// rewritten by the user's redirect rules.
+    pub fn process_url(&self, input: &str) -> NavigationTarget {
+        match self.classify_input(input.trim()) {
+            NavigationTarget::Url(url) => NavigationTarget::Url(self.rewrite_url(url)),
+            target => target,
+        }
+    }
+    
+    fn rewrite_url(&self, url: Url) -> Url {
+        let url = if self.settings.clean_urls { self.url_cleaner.clean(&url) } else { url };
+        match self.settings.redirects.apply(&url) {
+            Some((target, rule)) => {
+                log::info!("Redirect rule {:?}: {} -> {}", rule.name, url, target);
+                target
+            }
+            None => url,
+        }
+    }
+    
+    fn classify_input(&self, trimmed: &str) -> NavigationTarget {
+        
+        // `!gh tokio` (or `tokio !gh`) jumps straight to the bang's site
+        if let Some((bang, query)) = self.bangs.resolve(trimmed) {
+            return match Url::parse(&bang.url_for(&query)) {
+                Ok(url) => NavigationTarget::Url(url),
+                Err(e) => NavigationTarget::Invalid {
+                    input: trimmed.to_string(),
+                    reason: format!("Bang !{} has an invalid URL: {}", bang.trigger, e),
+                },
+            };
+        }
+        
+        // `w rust lifetimes` searches the engine with keyword `w`
+        if let Some((engine, query)) = self.keyword_search(trimmed) {
+            return NavigationTarget::Search {
+                query: query.to_string(),
+                engine: Some(engine.keyword.clone()),
+            };
+        }
+        
+        navigation::classify(trimmed)
+    }
+    
+    /// Where navigating to `input` goes, after HTTPS-only upgrades.
+    pub fn locate(&self, input: &str) -> Location {
+        let trimmed = input.trim();
+        let (url, display_url) = match self.process_url(trimmed) {
+            NavigationTarget::Url(url) => {
+                let (url, upgraded_from) = self.upgrade_to_https(url);
+                // Don't keep showing http:// for a page that's loading over https.
+                let display_url = if upgraded_from.is_some() { url.to_string() } else { trimmed.to_string() };
+                return Location { url: url.into(), display_url, upgraded_from };
+            }
+            NavigationTarget::File(url) => (url.into(), trimmed.to_string()),
+            NavigationTarget::Internal(page) if trimmed.is_empty() => {
+                (format!("minimalist:///{}", page), "New Tab".to_string())
+            }
+            // Internal pages are served by path, so minimalist://page loads as minimalist:///page
+            NavigationTarget::Internal(page) => {
+                (format!("minimalist:///{}", page), format!("minimalist://{}", page))
+            }
+            NavigationTarget::Search { query, engine } => {
+                let engine = engine
+                    .and_then(|k| self.settings.search_engines.find(&k))
+                    .unwrap_or_else(|| self.default_search_engine());
+                (engine.search_url(&query), trimmed.to_string())
+            }
+            NavigationTarget::Invalid { reason, .. } => (
+                format!("minimalist:///error?reason={}", urlencoding::encode(&reason)),
+                trimmed.to_string(),
+            ),
+        };
+        Location { url, display_url, upgraded_from: None }
+    }
+    
+    /// Switches http to https in HTTPS-only mode, returning the original URL
+    /// if it did. Local hosts and the user's exceptions stay on http.
+    fn upgrade_to_https(&self, mut url: Url) -> (Url, Option<String>) {
+        if !self.settings.https_only || url.scheme() != "http" {
+            return (url, None);
+        }
+        let Some(host) = url.host_str().map(str::to_string) else {
+            return (url, None);
+        };
+        if self.settings.http_exceptions.contains(&host)
+            || self.http_allowed_hosts.contains(&host)
+            || Self::is_local_host(&host)
+        {
+            return (url, None);
+        }
+        
+        let original = url.to_string();
+        if url.set_scheme("https").is_err() {
+            return (url, None);
+        }
+        if url.port() == Some(80) {
+            let _ = url.set_port(None);
+        }
+        (url, Some(original))
+    }
+    
+    /// Loopback, private and link-local addresses, `localhost`, mDNS `.local`
+    /// names and single-label intranet hosts rarely have certificates.
+    fn is_local_host(host: &str) -> bool {
+        let bare = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = bare.parse::<IpAddr>() {
+            return match ip {
+                IpAddr::V4(v4) => v4.is_loopback() || v4.is_private() || v4.is_link_local(),
+                IpAddr::V6(v6) => v6.is_loopback() || (v6.segments()[0] & 0xfe00) == 0xfc00
+                    || (v6.segments()[0] & 0xffc0) == 0xfe80,
+            };
+        }
+        host == "localhost"
+            || host.ends_with(".localhost")
+            || host.ends_with(".local")
+            || !host.contains('.')
+    }
+    
+    pub fn keyword_search<'a>(&self, trimmed: &'a str) -> Option<(&SearchEngine, &'a str)> {
+        self.settings.search_engines.split_keyword(trimmed)
+    }
+    
+    pub fn search_url(&self, query: &str) -> String {
+        self.default_search_engine().search_url(query)
+    }
+    
+    pub fn default_search_engine(&self) -> &SearchEngine {
+        self.settings.search_engines.default_engine()
     }
     
     fn get_title_from_url(url: &str) -> String {
@@ -205,11 +865,11 @@ impl BrowserCore {
     }
     
     pub fn toggle_booster_mode(&mut self) {
-        self.booster_mode = !self.booster_mode;
+        self.set_booster_enabled(!self.booster_mode);
     }
     
     pub fn toggle_ultimate_boost(&mut self) {
-        self.ultimate_boost = !self.ultimate_boost;
+        self.set_ultimate_boost_enabled(!self.ultimate_boost);
         if self.ultimate_boost {
             log::info!("🚀 ULTIMATE BOOST ACTIVATED - Near Zero Memory Mode");
         }
@@ -335,29 +995,128 @@ impl BrowserCore {
         }
     }
     
+    /// Discards one background tab. The active tab can't be discarded.
+    pub fn discard_tab(&mut self, tab_id: usize) -> bool {
+        if self.active_tab_id == Some(tab_id) {
+            return false;
+        }
+        match self.tabs.get_mut(&tab_id) {
+            Some(tab) => {
+                tab.discarded = true;
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Marks every tab except the active one as discarded. Returns how many
+    /// tabs were newly discarded.
+    pub fn discard_background_tabs(&mut self) -> usize {
+        let active = self.active_tab_id;
+        let mut count = 0;
+        for tab in self.tabs.values_mut() {
+            if Some(tab.id) != active && !tab.discarded {
+                tab.discarded = true;
+                count += 1;
+            }
+        }
+        if count > 0 {
+            self.memory_history.annotate(format!("Discarded {} background tab(s)", count));
+        }
+        count
+    }
+    
+    /// Discards background tabs left unused for longer than the
+    /// `discard_after_mins` setting. Returns how many were discarded.
+    pub fn discard_idle_tabs(&mut self) -> usize {
+        let minutes = self.settings.discard_after_mins;
+        if minutes == 0 {
+            return 0;
+        }
+        let cutoff = now_secs().saturating_sub(minutes * 60);
+        let active = self.active_tab_id;
+        let mut count = 0;
+        for tab in self.tabs.values_mut() {
+            if Some(tab.id) != active && !tab.discarded && tab.last_active <= cutoff {
+                tab.discarded = true;
+                count += 1;
+            }
+        }
+        if count > 0 {
+            log::info!("Discarded {} tab(s) idle for over {} minutes", count, minutes);
+            self.memory_history.annotate(format!("Discarded {} idle tab(s)", count));
+        }
+        count
+    }
+    
     pub fn get_active_tab(&self) -> Option<&TabData> {
         self.active_tab_id.and_then(|id| self.tabs.get(&id))
     }
     
     pub fn get_tabs(&self) -> Vec<&TabData> {
-        self.tabs.values().collect()
+        let mut tabs: Vec<&TabData> = self.tabs.values().collect();
+        tabs.sort_by_key(|t| t.id);
+        tabs
+    }
+    
+    pub fn snapshot(&self) -> BrowserState {
+        BrowserState {
+            tabs: self.get_tabs().into_iter().cloned().collect(),
+            active_tab_id: self.active_tab_id,
+            booster_active: self.booster_mode,
+            ultimate_boost_active: self.ultimate_boost,
+            flash_enabled: self.flash_handler.enabled,
+            flash_status: self.flash_handler.status(),
+            active_bookmarked: self.get_active_tab()
+                .is_some_and(|t| self.bookmarks.find_by_url(&t.url).is_some()),
+            search_engine_offer: self.search_engine_offer(),
+        }
     }
     
+    /// Counts WebKit's web and network processes where the process tree can
+    /// be read, since pages don't render in this process on Linux.
     pub fn get_memory_stats(&self) -> MemoryStats {
+        self.memory_stats_from(process_memory::sample())
+    }
+    
+    /// Stats for a breakdown sampled earlier, so callers can read /proc
+    /// without holding the lock on the core.
+    pub fn memory_stats_from(&self, breakdown: Option<MemoryBreakdown>) -> MemoryStats {
         MemoryStats {
-            total_mb: self.get_process_memory(),
+            total_mb: breakdown
+                .as_ref()
+                .map_or_else(|| self.get_process_memory(), MemoryBreakdown::total_mb),
             tab_count: self.tabs.len(),
             booster_active: self.booster_mode,
             ultimate_boost_active: self.ultimate_boost,
             limit_mb: self.memory_limit_mb,
+            breakdown,
         }
     }
     
+    /// Keeps a sample taken with `process_memory::sample` for
+    /// minimalist://memory's graph.
+    pub fn record_memory_sample(&mut self, breakdown: Option<MemoryBreakdown>) -> MemoryStats {
+        let stats = self.memory_stats_from(breakdown);
+        self.memory_history.record(&stats);
+        stats
+    }
+    
+    pub fn get_memory_history(&self) -> &MemoryHistory {
+        &self.memory_history
+    }
+    
     pub fn set_booster_enabled(&mut self, enabled: bool) {
+        if self.booster_mode != enabled {
+            self.memory_history.annotate(if enabled { "Booster on" } else { "Booster off" });
+        }
         self.booster_mode = enabled;
     }
     
     pub fn set_ultimate_boost_enabled(&mut self, enabled: bool) {
+        if self.ultimate_boost != enabled {
+            self.memory_history.annotate(if enabled { "Ultimate boost on" } else { "Ultimate boost off" });
+        }
         self.ultimate_boost = enabled;
     }
     
@@ -365,6 +1124,14 @@ impl BrowserCore {
         self.flash_handler.enabled = enabled;
     }
     
+    pub fn is_flash_enabled(&self) -> bool {
+        self.flash_handler.enabled
+    }
+    
+    pub fn get_flash_script(&self) -> String {
+        self.flash_handler.get_injection_script()
+    }
+    
     pub fn get_flash_status(&self) -> String {
         self.flash_handler.status()
     }
@@ -405,7 +1172,7 @@ impl BrowserCore {
     
     #[cfg(target_os = "linux")]
     fn get_process_memory(&self) -> usize {
-        // Linux memory detection from /proc/self/status
+        // Fallback when the process tree can't be walked: this process only
         std::fs::read_to_string("/proc/self/status")
             .ok()
             .and_then(|content| {
@@ -429,4 +1196,37 @@ pub struct MemoryStats {
     pub booster_active: bool,
     pub ultimate_boost_active: bool,
     pub limit_mb: usize,
+    /// Per-process figures; `None` where only this process could be measured.
+    #[serde(default)]
+    pub breakdown: Option<MemoryBreakdown>,
+}
+
+/// The result of resolving address-bar input for a navigation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub url: String,
+    pub display_url: String,
+    /// Set when HTTPS-only mode replaced an http URL.
+    pub upgraded_from: Option<String>,
+}
+
+impl Location {
+    fn history_entry(&self, title: &str) -> HistoryEntry {
+        let mut entry = HistoryEntry::new(&self.url, &self.display_url, title);
+        entry.upgraded_from = self.upgraded_from.clone();
+        entry
+    }
+}
+
+/// Everything the shell needs to render itself.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BrowserState {
+    pub tabs: Vec<TabData>,
+    pub active_tab_id: Option<usize>,
+    pub booster_active: bool,
+    pub ultimate_boost_active: bool,
+    pub flash_enabled: bool,
+    pub flash_status: String,
+    pub active_bookmarked: bool,
+    pub search_engine_offer: Option<DiscoveredEngine>,
 }
\ No newline at end of file
@@ -0,0 +1,305 @@
+// browsing_history.rs - Visit log persisted in browser_data/history.jsonl
+use serde::{Serialize, Deserialize};
+use crate::storage;
+use crate::tab_history::now_secs;
+
+const HISTORY_FILE: &str = "history.jsonl";
+/// Visits older than this are dropped when the log is loaded.
+const RETENTION_SECS: u64 = 90 * 24 * 60 * 60;
+
+/// How the user got to a page.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transition {
+    /// Typed or pasted into the address bar, or opened from an internal page.
+    Typed,
+    /// Followed a link inside a page.
+    Link,
+    BackForward,
+    Reload,
+    /// Reached through a redirect rather than by the user.
+    Redirect,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Visit {
+    pub id: u64,
+    pub url: String,
+    pub title: String,
+    pub visited_at: u64,
+    pub transition: Transition,
+    pub tab_id: usize,
+}
+
+/// A line of the history file: a visit, or a title the page reported after it was recorded.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LogLine {
+    Visit(Visit),
+    Title { title_of: u64, title: String },
+}
+
+#[derive(Default)]
+pub struct BrowsingHistory {
+    visits: Vec<Visit>,
+    next_id: u64,
+    /// Only history from `load` is written out; a `default` one stays in memory.
+    persistent: bool,
+}
+
+impl BrowsingHistory {
+    /// Title records are folded into their visits, so the file is rewritten
+    /// whenever it held any, or visits past retention.
+    pub fn load() -> Self {
+        let lines: Vec<LogLine> = storage::load_json_lines(HISTORY_FILE).unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable history: {}", e);
+            Vec::new()
+        });
+        let logged = lines.len();
+        let mut history = Self::replay(lines, now_secs());
+        history.persistent = true;
+        if history.visits.len() != logged {
+            history.save();
+        }
+        history
+    }
+
+    fn replay(lines: Vec<LogLine>, now: u64) -> Self {
+        let mut visits: Vec<Visit> = Vec::new();
+        for line in lines {
+            match line {
+                LogLine::Visit(visit) => visits.push(visit),
+                LogLine::Title { title_of, title } => {
+                    if let Some(visit) = visits.iter_mut().rev().find(|v| v.id == title_of) {
+                        visit.title = title;
+                    }
+                }
+            }
+        }
+        let cutoff = now.saturating_sub(RETENTION_SECS);
+        visits.retain(|v| v.visited_at >= cutoff);
+        Self {
+            next_id: visits.iter().map(|v| v.id).max().unwrap_or(0),
+            visits,
+            persistent: false,
+        }
+    }
+
+    fn save(&self) {
+        if !self.persistent {
+            return;
+        }
+        if let Err(e) = storage::save_json_lines(HISTORY_FILE, &self.visits) {
+            log::warn!("Failed to save history: {}", e);
+        }
+    }
+
+    fn append(&self, line: &LogLine) {
+        if !self.persistent {
+            return;
+        }
+        if let Err(e) = storage::append_json_line(HISTORY_FILE, line) {
+            log::warn!("Failed to record visit: {}", e);
+        }
+    }
+
+    /// Whether a URL belongs in history at all; internal pages don't.
+    pub fn is_recordable(url: &str) -> bool {
+        !(url.is_empty() || url.starts_with("minimalist:") || url == "about:blank")
+    }
+
+    pub fn record(&mut self, url: &str, title: &str, transition: Transition, tab_id: usize) {
+        if !Self::is_recordable(url) {
+            return;
+        }
+        self.next_id += 1;
+        let visit = Visit {
+            id: self.next_id,
+            url: url.to_string(),
+            title: title.to_string(),
+            visited_at: now_secs(),
+            transition,
+            tab_id,
+        };
+        self.append(&LogLine::Visit(visit.clone()));
+        self.visits.push(visit);
+    }
+
+    /// Fills in the title once the page reports it, on the tab's latest visit to `url`.
+    /// The change is appended to the file; `load` folds it into the visit.
+    pub fn update_title(&mut self, tab_id: usize, url: &str, title: &str) {
+        let latest = self.visits
+            .iter_mut()
+            .rev()
+            .find(|v| v.tab_id == tab_id && v.url == url);
+        let Some(visit) = latest else {
+            return;
+        };
+        if visit.title == title {
+            return;
+        }
+        visit.title = title.to_string();
+        let line = LogLine::Title { title_of: visit.id, title: title.to_string() };
+        self.append(&line);
+    }
+
+    /// Newest first, optionally filtered by a case-insensitive match on URL or title.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Visit> {
+        let query = query.trim().to_lowercase();
+        self.visits
+            .iter()
+            .rev()
+            .filter(|v| {
+                query.is_empty()
+                    || v.url.to_lowercase().contains(&query)
+                    || v.title.to_lowercase().contains(&query)
+            })
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn visits(&self) -> &[Visit] {
+        &self.visits
+    }
+
+    pub fn delete_visit(&mut self, id: u64) -> bool {
+        let before = self.visits.len();
+        self.visits.retain(|v| v.id != id);
+        let removed = self.visits.len() != before;
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    /// Deletes visits with `from <= visited_at < to`. Returns how many were removed.
+    pub fn delete_range(&mut self, from: u64, to: u64) -> usize {
+        let before = self.visits.len();
+        self.visits.retain(|v| v.visited_at < from || v.visited_at >= to);
+        let removed = before - self.visits.len();
+        if removed > 0 {
+            self.save();
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn visit(id: u64, url: &str, visited_at: u64) -> LogLine {
+        LogLine::Visit(Visit {
+            id,
+            url: url.to_string(),
+            title: String::new(),
+            visited_at,
+            transition: Transition::Link,
+            tab_id: 1,
+        })
+    }
+
+    fn history(visited_at: &[u64]) -> BrowsingHistory {
+        let lines = visited_at
+            .iter()
+            .enumerate()
+            .map(|(i, &at)| visit(i as u64 + 1, &format!("https://{}.test/", i + 1), at))
+            .collect();
+        BrowsingHistory::replay(lines, NOW)
+    }
+
+    fn ids(history: &BrowsingHistory) -> Vec<u64> {
+        history.visits().iter().map(|v| v.id).collect()
+    }
+
+    #[test]
+    fn records_web_pages_only() {
+        let mut history = BrowsingHistory::default();
+        let cases = [
+            ("https://example.com/", true),
+            ("http://example.com/a?b", true),
+            ("minimalist://newtab", false),
+            ("about:blank", false),
+            ("", false),
+        ];
+        for (url, recorded) in cases {
+            let before = history.visits().len();
+            history.record(url, "Title", Transition::Typed, 7);
+            assert_eq!(history.visits().len() - before, recorded as usize, "{:?}", url);
+        }
+
+        let visits = history.visits();
+        assert_eq!(ids(&history), vec![1, 2]);
+        assert_eq!(visits[1].url, "http://example.com/a?b");
+        assert_eq!((visits[1].tab_id, visits[1].transition), (7, Transition::Typed));
+        assert!(visits[1].visited_at >= visits[0].visited_at);
+    }
+
+    #[test]
+    fn prunes_visits_past_retention() {
+        let cutoff = NOW - RETENTION_SECS;
+        let history = history(&[cutoff - 1, cutoff, NOW]);
+        assert_eq!(ids(&history), vec![2, 3]);
+
+        // Ids carry on from the newest kept visit, even when older ones were pruned.
+        let mut history = history;
+        history.record("https://next.test/", "", Transition::Link, 1);
+        assert_eq!(ids(&history), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn replays_title_updates() {
+        let raw = [
+            r#"{"id":1,"url":"https://a.test/","title":"a.test","visited_at":1700000000,"transition":"typed","tab_id":1}"#,
+            r#"{"title_of":1,"title":"Page A"}"#,
+            r#"{"title_of":9,"title":"No such visit"}"#,
+        ];
+        let lines = raw.iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let history = BrowsingHistory::replay(lines, NOW);
+        assert_eq!(ids(&history), vec![1]);
+        assert_eq!(history.visits()[0].title, "Page A");
+    }
+
+    #[test]
+    fn updates_title_of_latest_matching_visit() {
+        let mut history = BrowsingHistory::default();
+        history.record("https://a.test/", "a.test", Transition::Typed, 1);
+        history.record("https://a.test/", "a.test", Transition::Reload, 1);
+        history.record("https://a.test/", "a.test", Transition::Typed, 2);
+        history.update_title(1, "https://a.test/", "Page A");
+        history.update_title(1, "https://b.test/", "Page B");
+
+        let titles: Vec<&str> = history.visits().iter().map(|v| v.title.as_str()).collect();
+        assert_eq!(titles, vec!["a.test", "Page A", "a.test"]);
+    }
+
+    #[test]
+    fn deletes_visits() {
+        let mut history = history(&[NOW, NOW, NOW]);
+        assert!(history.delete_visit(2));
+        assert!(!history.delete_visit(2));
+        assert_eq!(ids(&history), vec![1, 3]);
+    }
+
+    #[test]
+    fn deletes_range_including_start_excluding_end() {
+        let cases = [
+            (NOW - 10, NOW + 10, vec![]),
+            // `from` is inclusive
+            (NOW - 5, NOW + 10, vec![1]),
+            // `to` is exclusive
+            (NOW - 10, NOW + 5, vec![4]),
+            (NOW - 5, NOW + 5, vec![1, 4]),
+            (NOW + 5, NOW - 5, vec![1, 2, 3, 4]),
+        ];
+        for (from, to, kept) in cases {
+            let mut history = history(&[NOW - 10, NOW - 5, NOW, NOW + 5]);
+            let removed = history.delete_range(from, to);
+            assert_eq!(ids(&history), kept, "{}..{}", from, to);
+            assert_eq!(removed, 4 - kept.len(), "{}..{}", from, to);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+// content_index.rs - Inverted index over the text of visited pages
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use crate::storage;
+use crate::tab_history::now_secs;
+
+/// A log of added and removed documents, compacted when it is loaded.
+const INDEX_FILE: &str = "content_index.jsonl";
+/// Oldest documents are evicted beyond this.
+const MAX_DOCUMENTS: usize = 1000;
+/// Text kept per page for snippets; indexing also stops here.
+const MAX_TEXT_CHARS: usize = 10_000;
+/// The log is rewritten on load once it holds this many entries per live document.
+const COMPACT_RATIO: usize = 2;
+const SNIPPET_RADIUS: usize = 80;
+
+// BM25 tuning, with the usual defaults.
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+/// Each title occurrence counts as this many body occurrences.
+const TITLE_BOOST: u32 = 2;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "is", "it",
+    "of", "on", "or", "that", "the", "this", "to", "was", "with",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Document {
+    url: String,
+    title: String,
+    indexed_at: u64,
+    text: String,
+    length: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LogEntry {
+    Add { id: u32, document: Document },
+    Remove { id: u32 },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ContentHit {
+    pub url: String,
+    pub title: String,
+    pub indexed_at: u64,
+    pub score: f64,
+    /// HTML-escaped excerpt with matches wrapped in `<mark>`.
+    pub snippet: String,
+}
+
+/// The postings are rebuilt from the documents on load, so only documents are stored.
+#[derive(Default)]
+pub struct ContentIndex {
+    next_id: u32,
+    documents: HashMap<u32, Document>,
+    /// term -> (document id, term frequency)
+    postings: HashMap<String, Vec<(u32, u32)>>,
+    /// Only an index from `load` logs its changes; a `default` one stays in memory.
+    persistent: bool,
+}
+
+impl ContentIndex {
+    pub fn load() -> Self {
+        let entries: Vec<LogEntry> = storage::load_json_lines(INDEX_FILE).unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable content index: {}", e);
+            Vec::new()
+        });
+        let mut index = Self::default();
+        let logged = entries.len();
+        for entry in entries {
+            match entry {
+                LogEntry::Add { id, document } => {
+                    index.next_id = index.next_id.max(id);
+                    index.insert_document(id, document);
+                }
+                LogEntry::Remove { id } => index.remove_document(id),
+            }
+        }
+        index.persistent = true;
+        if logged > index.documents.len() * COMPACT_RATIO {
+            index.save();
+        }
+        index
+    }
+
+    /// Rewrites the log with just the live documents.
+    fn save(&self) {
+        if !self.persistent {
+            return;
+        }
+        let mut ids: Vec<u32> = self.documents.keys().copied().collect();
+        ids.sort_unstable();
+        let entries: Vec<LogEntry> = ids
+            .into_iter()
+            .map(|id| LogEntry::Add { id, document: self.documents[&id].clone() })
+            .collect();
+        if let Err(e) = storage::save_json_lines(INDEX_FILE, &entries) {
+            log::warn!("Failed to save content index: {}", e);
+        }
+    }
+
+    fn log(&self, entry: &LogEntry) {
+        if !self.persistent {
+            return;
+        }
+        if let Err(e) = storage::append_json_line(INDEX_FILE, entry) {
+            log::warn!("Failed to update content index: {}", e);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.documents.clear();
+        self.postings.clear();
+        self.save();
+    }
+
+    /// Indexes (or re-indexes) the text of `url`.
+    pub fn index_page(&mut self, url: &str, title: &str, text: &str) {
+        self.remove_url_quietly(url);
+
+        let text: String = collapse_whitespace(text).chars().take(MAX_TEXT_CHARS).collect();
+        let length = term_frequencies(&text, title).values().sum::<u32>() as usize;
+        if length == 0 {
+            return;
+        }
+
+        self.next_id = self.next_id.wrapping_add(1);
+        let id = self.next_id;
+        let document = Document {
+            url: url.to_string(),
+            title: title.to_string(),
+            indexed_at: now_secs(),
+            text,
+            length,
+        };
+        self.log(&LogEntry::Add { id, document: document.clone() });
+        self.insert_document(id, document);
+
+        while self.documents.len() > MAX_DOCUMENTS {
+            let oldest = self.documents.iter().min_by_key(|(_, d)| d.indexed_at).map(|(id, _)| *id);
+            match oldest {
+                Some(oldest) => self.remove_document(oldest),
+                None => break,
+            }
+        }
+    }
+
+    /// BM25-ranked search, optionally limited to pages indexed at or after `since`.
+    pub fn search(&self, query: &str, since: Option<u64>, limit: usize) -> Vec<ContentHit> {
+        let terms: Vec<String> = tokenize(query).collect();
+        if terms.is_empty() || self.documents.is_empty() {
+            return Vec::new();
+        }
+
+        let doc_count = self.documents.len() as f64;
+        let avg_length = self.documents.values().map(|d| d.length).sum::<usize>() as f64 / doc_count;
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let idf = ((doc_count - postings.len() as f64 + 0.5) / (postings.len() as f64 + 0.5) + 1.0).ln();
+            for &(id, tf) in postings {
+                let Some(doc) = self.documents.get(&id) else {
+                    continue;
+                };
+                if since.is_some_and(|since| doc.indexed_at < since) {
+                    continue;
+                }
+                let tf = tf as f64;
+                let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * doc.length as f64 / avg_length);
+                *scores.entry(id).or_default() += idf * tf * (BM25_K1 + 1.0) / (tf + norm);
+            }
+        }
+
+        let mut ranked: Vec<(u32, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+            .into_iter()
+            .take(limit)
+            .filter_map(|(id, score)| {
+                let doc = self.documents.get(&id)?;
+                Some(ContentHit {
+                    url: doc.url.clone(),
+                    title: doc.title.clone(),
+                    indexed_at: doc.indexed_at,
+                    score,
+                    snippet: snippet(&doc.text, &terms),
+                })
+            })
+            .collect()
+    }
+
+    /// Deletions rewrite the log rather than append to it, so the text of
+    /// deleted history doesn't linger on disk.
+    pub fn remove_url(&mut self, url: &str) {
+        if self.remove_url_quietly(url) {
+            self.save();
+        }
+    }
+
+    /// Drops pages indexed with `from <= indexed_at < to`, mirroring history deletion.
+    pub fn remove_range(&mut self, from: u64, to: u64) {
+        let doomed: Vec<u32> = self.documents
+            .iter()
+            .filter(|(_, d)| d.indexed_at >= from && d.indexed_at < to)
+            .map(|(id, _)| *id)
+            .collect();
+        if doomed.is_empty() {
+            return;
+        }
+        for id in doomed {
+            self.remove_document(id);
+        }
+        self.save();
+    }
+
+    fn remove_url_quietly(&mut self, url: &str) -> bool {
+        let existing = self.documents.iter().find(|(_, d)| d.url == url).map(|(id, _)| *id);
+        match existing {
+            Some(id) => {
+                self.remove_document(id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert_document(&mut self, id: u32, document: Document) {
+        for (term, tf) in term_frequencies(&document.text, &document.title) {
+            self.postings.entry(term).or_default().push((id, tf));
+        }
+        self.documents.insert(id, document);
+    }
+
+    fn remove_document(&mut self, id: u32) {
+        let Some(doc) = self.documents.remove(&id) else {
+            return;
+        };
+        self.log(&LogEntry::Remove { id });
+        for term in tokenize(&doc.text).chain(tokenize(&doc.title)) {
+            if let Some(list) = self.postings.get_mut(&term) {
+                list.retain(|(doc_id, _)| *doc_id != id);
+                if list.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+}
+
+fn term_frequencies(text: &str, title: &str) -> HashMap<String, u32> {
+    let mut frequencies: HashMap<String, u32> = HashMap::new();
+    for term in tokenize(text) {
+        *frequencies.entry(term).or_default() += 1;
+    }
+    // Title terms count extra so a page is found by what it's called.
+    for term in tokenize(title) {
+        *frequencies.entry(term).or_default() += TITLE_BOOST;
+    }
+    frequencies
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| (2..=40).contains(&t.chars().count()))
+        .map(str::to_lowercase)
+        .filter(|t| !STOP_WORDS.contains(&t.as_str()))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Cuts an excerpt around the first matching term and marks every match in it.
+fn snippet(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = text.to_lowercase().chars().collect();
+    // Lowercasing can change length for a few scripts; fall back to no highlighting then.
+    let needles: Vec<Vec<char>> = if lower.len() == chars.len() {
+        terms.iter().map(|t| t.chars().collect()).collect()
+    } else {
+        Vec::new()
+    };
+    // The term starting a word at `i`, if any.
+    let match_at = |i: usize| {
+        needles.iter().find(|needle| lower[i..].starts_with(needle) && is_word_start(&lower, i))
+    };
+
+    let first = (0..lower.len()).find(|&i| match_at(i).is_some());
+    let start = first.map_or(0, |i| i.saturating_sub(SNIPPET_RADIUS));
+    let end = (first.unwrap_or(0) + SNIPPET_RADIUS).max(start + 2 * SNIPPET_RADIUS).min(chars.len());
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let mut i = start;
+    while i < end {
+        match match_at(i) {
+            Some(needle) => {
+                let len = needle.len().min(end - i);
+                out.push_str("<mark>");
+                out.push_str(&escape_html(&chars[i..i + len].iter().collect::<String>()));
+                out.push_str("</mark>");
+                i += len;
+            }
+            None => {
+                out.push_str(&escape_html(&chars[i].to_string()));
+                i += 1;
+            }
+        }
+    }
+    if end < chars.len() {
+        out.push('…');
+    }
+    out
+}
+
+fn is_word_start(chars: &[char], i: usize) -> bool {
+    i == 0 || !chars[i - 1].is_alphanumeric()
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(query: &str) -> Vec<String> {
+        tokenize(query).collect()
+    }
+
+    fn index(pages: &[(&str, &str, &str)]) -> ContentIndex {
+        let mut index = ContentIndex::default();
+        for (url, title, text) in pages {
+            index.index_page(url, title, text);
+        }
+        index
+    }
+
+    fn ranked(index: &ContentIndex, query: &str) -> Vec<String> {
+        index.search(query, None, 10).into_iter().map(|hit| hit.url).collect()
+    }
+
+    #[test]
+    fn snippets() {
+        let long = format!("{} needle {}", "x ".repeat(100), "y ".repeat(100));
+        let cases = [
+            // Matches are marked at word starts only, case-insensitively
+            ("The Rust book", "rust", "The <mark>Rust</mark> book"),
+            ("trust rusty rust", "rust", "trust <mark>rust</mark>y <mark>rust</mark>"),
+            ("cats and dogs", "dogs cats", "<mark>cats</mark> and <mark>dogs</mark>"),
+            // Text is escaped, marks are not
+            ("<b>rust</b> & co", "rust", "&lt;b&gt;<mark>rust</mark>&lt;/b&gt; &amp; co"),
+            // No match starts at the beginning
+            ("nothing here", "rust", "nothing here"),
+        ];
+        for (text, query, expected) in cases {
+            assert_eq!(snippet(text, &terms(query)), expected, "{:?} in {:?}", query, text);
+        }
+
+        // Long texts are cut around the first match, with ellipses.
+        let cut = snippet(&long, &terms("needle"));
+        assert!(cut.starts_with('…') && cut.ends_with('…'), "{}", cut);
+        assert!(cut.contains("<mark>needle</mark>"));
+        assert!(cut.chars().count() < long.chars().count());
+    }
+
+    #[test]
+    fn snippet_of_long_text_keeps_its_window() {
+        let filler = "lorem ipsum dolor sit amet ".repeat(2_000);
+        let window = |from: usize, to: usize| filler.chars().skip(from).take(to - from).collect::<String>();
+
+        // Without a match the excerpt is the start of the text.
+        let expected = format!("{}…", window(0, 2 * SNIPPET_RADIUS));
+        assert_eq!(snippet(&filler, &terms("missing absent nowhere")), expected);
+
+        // A match at the very end is shown with what precedes it.
+        let at = filler.chars().count();
+        let expected = format!("…{}<mark>needle</mark>", window(at - SNIPPET_RADIUS, at));
+        assert_eq!(snippet(&format!("{}needle", filler), &terms("needle")), expected);
+    }
+
+    #[test]
+    fn ranking() {
+        let index = index(&[
+            ("https://a.test/", "Cooking", "pasta recipes and one mention of rust"),
+            ("https://b.test/", "Rust guide", "learn the language"),
+            ("https://c.test/", "Metals", "rust rust rust on iron and steel"),
+            ("https://d.test/", "Unrelated", "nothing to see"),
+        ]);
+        let cases: [(&str, &[&str]); 5] = [
+            ("rust", &["https://c.test/", "https://b.test/", "https://a.test/"]),
+            ("RUST guide", &["https://b.test/", "https://c.test/", "https://a.test/"]),
+            ("pasta", &["https://a.test/"]),
+            // Stop words and unknown terms match nothing
+            ("the of and", &[]),
+            ("zebra", &[]),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(ranked(&index, query), expected, "{:?}", query);
+        }
+    }
+
+    #[test]
+    fn reindexing_replaces_the_page() {
+        let mut index = index(&[("https://a.test/", "Old", "first version mentions apples")]);
+        index.index_page("https://a.test/", "New", "second version mentions pears");
+        assert!(ranked(&index, "apples").is_empty());
+        assert_eq!(ranked(&index, "pears"), ["https://a.test/"]);
+        assert_eq!(index.documents.len(), 1);
+    }
+
+    #[test]
+    fn since_filters_older_pages() {
+        let index = index(&[("https://a.test/", "Page", "searchable words")]);
+        let indexed_at = index.documents.values().next().unwrap().indexed_at;
+        assert_eq!(index.search("searchable", Some(indexed_at), 10).len(), 1);
+        assert!(index.search("searchable", Some(indexed_at + 1), 10).is_empty());
+    }
+}
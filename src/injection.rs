@@ -0,0 +1,203 @@
+// injection.rs - Composes the scripts injected into page frames
+use crate::browser_core::BrowserCore;
+
+/// Stages of the page script, in injection order. Flash runs first so embeds
+/// are replaced before the boosters start lazy-loading and pruning the DOM;
+/// text extraction runs last and waits for the page to finish loading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptStage {
+    Flash,
+    Booster,
+    UltimateBoost,
+    ContentExtract,
+    OpenSearchDiscovery,
+}
+
+impl ScriptStage {
+    pub const ORDER: [ScriptStage; 5] = [
+        ScriptStage::Flash,
+        ScriptStage::Booster,
+        ScriptStage::UltimateBoost,
+        ScriptStage::ContentExtract,
+        ScriptStage::OpenSearchDiscovery,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            ScriptStage::Flash => "flash",
+            ScriptStage::Booster => "booster",
+            ScriptStage::UltimateBoost => "ultimate_boost",
+            ScriptStage::ContentExtract => "content_extract",
+            ScriptStage::OpenSearchDiscovery => "opensearch_discovery",
+        }
+    }
+
+    fn source(&self, core: &BrowserCore) -> String {
+        match self {
+            ScriptStage::Flash => core.get_flash_script(),
+            ScriptStage::Booster => core.get_booster_script().to_string(),
+            ScriptStage::UltimateBoost => core.get_ultimate_boost_script().to_string(),
+            ScriptStage::ContentExtract if core.get_settings().index_page_content => {
+                CONTENT_EXTRACT_SCRIPT.to_string()
+            }
+            ScriptStage::ContentExtract => String::new(),
+            ScriptStage::OpenSearchDiscovery => OPENSEARCH_DISCOVERY_SCRIPT.to_string(),
+        }
+    }
+}
+
+/// Builds the script for the currently enabled modes, so it can be
+/// re-applied to a live page after any toggle: stages already running are
+/// left alone and stages that were switched off are torn down first.
+pub fn compose(core: &BrowserCore) -> String {
+    let stages: Vec<(ScriptStage, String)> = ScriptStage::ORDER
+        .iter()
+        .map(|stage| (*stage, stage.source(core)))
+        .filter(|(_, source)| !source.trim().is_empty())
+        .collect();
+    let enabled = stages
+        .iter()
+        .map(|(stage, _)| format!("'{}'", stage.key()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut script = teardown_disabled(&enabled);
+    for (stage, source) in &stages {
+        script.push('\n');
+        script.push_str(&wrap_stage(stage.key(), source));
+    }
+    script
+}
+
+fn teardown_disabled(enabled: &str) -> String {
+    format!(
+        r#"(function() {{
+    const stages = window.__minimalistStages = window.__minimalistStages || {{}};
+    const enabled = [{enabled}];
+    Object.keys(stages).forEach(key => {{
+        if (enabled.includes(key)) return;
+        try {{ stages[key].teardown(); }} catch (e) {{}}
+        delete stages[key];
+    }});
+}})();"#,
+        enabled = enabled,
+    )
+}
+
+/// Runs a stage at most once per document. Its `setInterval` and
+/// `setTimeout` are shadowed so teardown can cancel every timer it started,
+/// and `onTeardown` registers any other cleanup.
+fn wrap_stage(key: &str, source: &str) -> String {
+    format!(
+        r#"(function() {{
+    const stages = window.__minimalistStages = window.__minimalistStages || {{}};
+    if (stages['{key}']) return;
+    const timers = [];
+    const cleanups = [];
+    let stopped = false;
+    const track = start => (...args) => {{
+        if (stopped) return 0;
+        const id = start(...args);
+        timers.push(id);
+        return id;
+    }};
+    const setInterval = track(window.setInterval.bind(window));
+    const setTimeout = track(window.setTimeout.bind(window));
+    const onTeardown = fn => cleanups.push(fn);
+    const run = () => {{ {source} }};
+    stages['{key}'] = {{
+        teardown() {{
+            stopped = true;
+            document.removeEventListener('DOMContentLoaded', run);
+            timers.forEach(id => window.clearTimeout(id));
+            cleanups.forEach(fn => {{ try {{ fn(); }} catch (e) {{}} }});
+        }}
+    }};
+    if (document.readyState === 'loading') {{
+        document.addEventListener('DOMContentLoaded', run, {{ once: true }});
+    }} else {{
+        run();
+    }}
+}})();"#,
+        key = key,
+        source = source,
+    )
+}
+
+/// Sends the page's visible text to the shell for the content index once
+/// loading settles. The shell forwards it as an `index_page` command.
+const CONTENT_EXTRACT_SCRIPT: &str = r#"
+const send = () => {
+    if (!document.body) return;
+    const text = (document.body.innerText || '').slice(0, 20000);
+    if (!text.trim()) return;
+    window.top.postMessage({ minimalist: 'page_text', text: text }, '*');
+};
+const later = () => setTimeout(send, 1500);
+if (document.readyState === 'complete') {
+    later();
+} else {
+    window.addEventListener('load', later, { once: true });
+    onTeardown(() => window.removeEventListener('load', later));
+}
+"#;
+
+/// Fetches the OpenSearch descriptors the page links to and hands them to
+/// the shell. The page fetches them itself, through WebKit's own network
+/// stack, and only from its own origin, which is all BrowserCore accepts.
+/// Each descriptor is fetched at most once per browsing session.
+const OPENSEARCH_DISCOVERY_SCRIPT: &str = r#"
+const links = document.querySelectorAll('link[rel~="search"][type="application/opensearchdescription+xml"][href]');
+Array.from(links).slice(0, 3).forEach(link => {
+    const href = link.href;
+    if (new URL(href).origin !== location.origin) return;
+    const seenKey = 'minimalist-opensearch:' + href;
+    try {
+        if (sessionStorage.getItem(seenKey)) return;
+        sessionStorage.setItem(seenKey, '1');
+    } catch (e) {}
+    fetch(href, { credentials: 'same-origin' })
+        .then(r => r.ok ? r.text() : Promise.reject(r.status))
+        .then(xml => {
+            if (xml.length > 65536) return;
+            window.top.postMessage({ minimalist: 'opensearch', descriptor_url: href, xml: xml }, '*');
+        })
+        .catch(() => {});
+});
+"#;
+
+/// User script registered for every frame. In tab frames, the shell's direct
+/// children, it asks the shell for the composed script as soon as the
+/// document starts, which also works for cross-origin pages the shell cannot
+/// reach into directly. It also reports where the frame navigates, and
+/// whether a click or key press started it, so history sees link clicks on
+/// any site. Frames nested inside pages are left alone.
+pub const FRAME_BOOTSTRAP: &str = r#"
+(function() {
+    if (window.top === window || window.parent !== window.top || window.__minimalistBootstrap) return;
+    window.__minimalistBootstrap = true;
+
+    // A navigation starting within a second of a real click or key press
+    // counts as the user's; anything else is a redirect.
+    let gestureAt = 0;
+    ['click', 'keydown', 'submit'].forEach(type => window.addEventListener(type, (e) => {
+        if (e.isTrusted) gestureAt = Date.now();
+    }, true));
+    window.addEventListener('beforeunload', () => {
+        window.top.postMessage({ minimalist: 'leaving', user_initiated: Date.now() - gestureAt < 1000 }, '*');
+    });
+    window.addEventListener('load', () => {
+        window.top.postMessage({ minimalist: 'page_loaded', url: location.href, title: document.title }, '*');
+    });
+
+    window.addEventListener('message', (e) => {
+        if (e.source !== window.top || !e.data || e.data.minimalist !== 'inject') return;
+        try {
+            (0, eval)(e.data.script);
+        } catch (err) {
+            console.warn('Minimalist script injection failed', err);
+        }
+    });
+    window.top.postMessage({ minimalist: 'request_injection' }, '*');
+})();
+"#;
@@ -0,0 +1,680 @@
+// ipc.rs - Typed command/response protocol between shell.html and BrowserCore
+use serde::{Serialize, Deserialize};
+use url::Url;
+use crate::bangs::{Bang, BangEntry, BangError, BangTable};
+use crate::bookmark_transfer::{self, ImportSummary};
+use crate::bookmarks::{BookmarkError, BookmarkStore, BookmarkUpdate};
+use crate::browser_core::{BrowserCore, BrowserState, MemoryStats, TabData};
+use crate::browsing_history::Visit;
+use crate::content_index::ContentHit;
+use crate::injection;
+use crate::memory_watchdog::{Mitigation, PressureLevel};
+use crate::opensearch::DiscoveredEngine;
+use crate::omnibox::{self, RemoteSuggest, Suggestion};
+use crate::redirects::{RedirectError, RedirectRule, RedirectRules};
+use crate::search_engines::{SearchEngine, SearchEngineError, SearchEngineRegistry};
+use crate::settings::Settings;
+
+const DEFAULT_HISTORY_LIMIT: usize = 500;
+const DEFAULT_CONTENT_RESULTS: usize = 50;
+const DEFAULT_SUGGESTIONS: usize = 8;
+
+/// A message posted by the shell via `window.ipc.postMessage`.
+///
+/// `request_id` is echoed back in the reply so the shell can resolve the matching promise.
+#[derive(Deserialize, Debug)]
+pub struct IpcRequest {
+    #[serde(default)]
+    pub request_id: Option<u64>,
+    #[serde(flatten)]
+    pub command: IpcCommand,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum IpcCommand {
+    GetState,
+    CreateTab {
+        #[serde(default)]
+        url: Option<String>,
+    },
+    CloseTab {
+        tab_id: usize,
+    },
+    SwitchTab {
+        tab_id: usize,
+    },
+    Navigate {
+        #[serde(default)]
+        tab_id: Option<usize>,
+        url: String,
+    },
+    PageLoaded {
+        tab_id: usize,
+        url: String,
+        #[serde(default)]
+        title: Option<String>,
+        /// The user followed a link to get here, as opposed to a redirect.
+        #[serde(default)]
+        user_initiated: bool,
+    },
+    Go {
+        #[serde(default)]
+        tab_id: Option<usize>,
+        delta: isize,
+    },
+    SetScroll {
+        tab_id: usize,
+        x: f64,
+        y: f64,
+    },
+    /// Unloads a background tab until it is switched to again.
+    DiscardTab {
+        tab_id: usize,
+    },
+    /// Asks WebKit to release what memory it can; handled by the main loop.
+    CollectGarbage,
+    ToggleBooster,
+    SetBooster {
+        enabled: bool,
+    },
+    ToggleUltimateBoost,
+    SetUltimateBoost {
+        enabled: bool,
+    },
+    SetFlashEnabled {
+        enabled: bool,
+    },
+    GetMemoryStats,
+    GetInjectionScript,
+    GetSettings,
+    SetRestoreSession {
+        enabled: bool,
+    },
+    GetRecoveredTabs,
+    RestoreTabs {
+        tab_ids: Vec<usize>,
+    },
+    DiscardRecovered,
+    GetBookmarks,
+    AddBookmark {
+        url: String,
+        #[serde(default)]
+        title: String,
+        #[serde(default)]
+        folder_id: Option<u64>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    UpdateBookmark {
+        id: u64,
+        #[serde(flatten)]
+        update: BookmarkUpdate,
+    },
+    MoveBookmark {
+        id: u64,
+        folder_id: Option<u64>,
+    },
+    DeleteBookmark {
+        id: u64,
+    },
+    /// Bookmarks the tab's page, or removes the bookmark if it already exists.
+    ToggleBookmark {
+        tab_id: usize,
+    },
+    AddBookmarkFolder {
+        name: String,
+        #[serde(default)]
+        parent_id: Option<u64>,
+    },
+    RenameBookmarkFolder {
+        id: u64,
+        name: String,
+    },
+    MoveBookmarkFolder {
+        id: u64,
+        parent_id: Option<u64>,
+    },
+    DeleteBookmarkFolder {
+        id: u64,
+    },
+    /// `content` is a Netscape HTML, Chrome JSON or Firefox JSON bookmark file.
+    ImportBookmarks {
+        content: String,
+    },
+    ExportBookmarks,
+    /// Reloads a tab's page, background tabs included. The shell does the
+    /// loading when it gets the `reload_tab` reply.
+    ReloadTab {
+        tab_id: usize,
+    },
+    GetHistory {
+        #[serde(default)]
+        query: String,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    DeleteHistoryVisit {
+        id: u64,
+    },
+    /// Deletes visits with `from <= visited_at < to` (Unix seconds).
+    DeleteHistoryRange {
+        from: u64,
+        to: u64,
+    },
+    /// Page text captured by the content-extract injection stage in tab
+    /// `tab_id`, whose document has the message origin `origin`. The page is
+    /// indexed under the tab's own URL and title, never ones it supplies.
+    IndexPage {
+        tab_id: usize,
+        origin: String,
+        text: String,
+    },
+    /// Full-text search over indexed pages; `since` is Unix seconds.
+    SearchContent {
+        query: String,
+        #[serde(default)]
+        since: Option<u64>,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    SetIndexPageContent {
+        enabled: bool,
+    },
+    SetCleanUrls {
+        enabled: bool,
+    },
+    SetHttpsOnly {
+        enabled: bool,
+    },
+    /// Idle minutes before background tabs are discarded; 0 turns it off.
+    SetDiscardAfter {
+        minutes: u64,
+    },
+    SetAutoUltimateBoost {
+        enabled: bool,
+    },
+    RemoveHttpException {
+        host: String,
+    },
+    /// The shell's probe of an upgraded https URL failed to connect.
+    HttpsUpgradeFailed {
+        tab_id: usize,
+        url: String,
+    },
+    /// Sent by the HTTPS-only interstitial.
+    ContinueOverHttp {
+        #[serde(default)]
+        tab_id: Option<usize>,
+        url: String,
+        #[serde(default)]
+        always: bool,
+    },
+    ClearContentIndex,
+    /// Address bar completions for partially typed input.
+    GetSuggestions {
+        input: String,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    AddSearchEngine {
+        #[serde(flatten)]
+        engine: SearchEngine,
+    },
+    RemoveSearchEngine {
+        keyword: String,
+    },
+    SetDefaultSearchEngine {
+        keyword: String,
+    },
+    /// An OpenSearch descriptor the page in `tab_id` links to, fetched by
+    /// the page itself and reported from a document on `origin`.
+    DiscoverSearchEngine {
+        tab_id: usize,
+        origin: String,
+        descriptor_url: String,
+        xml: String,
+    },
+    GetDiscoveredSearchEngines,
+    InstallSearchEngine {
+        descriptor_url: String,
+        #[serde(default)]
+        keyword: Option<String>,
+    },
+    DismissSearchEngine {
+        descriptor_url: String,
+    },
+    GetBangs,
+    /// Adds a bang or replaces the one with the same trigger.
+    SetBang {
+        #[serde(flatten)]
+        bang: Bang,
+    },
+    RemoveBang {
+        trigger: String,
+    },
+    GetRedirects,
+    /// Adds the rule when its id is 0, otherwise replaces the rule with that id.
+    SaveRedirect {
+        #[serde(flatten)]
+        rule: RedirectRule,
+    },
+    RemoveRedirect {
+        id: u64,
+    },
+    SetRedirectEnabled {
+        id: u64,
+        enabled: bool,
+    },
+    /// Where `url` would be redirected, by `rule` alone or by the enabled rules.
+    TestRedirect {
+        url: String,
+        #[serde(default)]
+        rule: Option<RedirectRule>,
+    },
+}
+
+impl IpcCommand {
+    /// Whether the command changes anything that belongs in the saved session.
+    pub fn changes_session(&self) -> bool {
+        matches!(
+            self,
+            IpcCommand::CreateTab { .. }
+                | IpcCommand::CloseTab { .. }
+                | IpcCommand::SwitchTab { .. }
+                | IpcCommand::Navigate { .. }
+                | IpcCommand::PageLoaded { .. }
+                | IpcCommand::Go { .. }
+                | IpcCommand::SetScroll { .. }
+                | IpcCommand::DiscardTab { .. }
+                | IpcCommand::ToggleBooster
+                | IpcCommand::SetBooster { .. }
+                | IpcCommand::ToggleUltimateBoost
+                | IpcCommand::SetUltimateBoost { .. }
+                | IpcCommand::SetFlashEnabled { .. }
+                | IpcCommand::RestoreTabs { .. }
+                | IpcCommand::HttpsUpgradeFailed { .. }
+                | IpcCommand::ContinueOverHttp { .. }
+        )
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IpcResponse {
+    State(BrowserState),
+    MemoryStats(MemoryStats),
+    Injection { script: String },
+    Settings(Settings),
+    RecoveredTabs { tabs: Vec<TabData> },
+    Bookmarks(BookmarkStore),
+    BookmarkImport {
+        summary: ImportSummary,
+        store: BookmarkStore,
+    },
+    BookmarkExport {
+        html: String,
+        path: Option<String>,
+    },
+    History { visits: Vec<Visit> },
+    ContentResults { results: Vec<ContentHit> },
+    /// `input` is echoed so the shell can drop replies for text it no longer shows.
+    Suggestions {
+        input: String,
+        suggestions: Vec<Suggestion>,
+        remote: Option<RemoteSuggest>,
+    },
+    DiscoveredSearchEngines { engines: Vec<DiscoveredEngine> },
+    Bangs { bangs: Vec<BangEntry> },
+    Redirects { rules: Vec<RedirectRule> },
+    /// `url` is `None` when no rule matched.
+    RedirectTest {
+        url: Option<String>,
+        rule: Option<String>,
+    },
+    /// Asks the shell to load the tab's page again.
+    ReloadTab { tab_id: usize },
+    /// Pushed by the memory watchdog, never sent as a reply.
+    MemoryPressure {
+        level: PressureLevel,
+        total_mb: usize,
+        limit_mb: usize,
+        mitigation: Option<Mitigation>,
+    },
+    Error { message: String },
+}
+
+#[derive(Serialize)]
+struct IpcReply<'a> {
+    request_id: Option<u64>,
+    #[serde(flatten)]
+    response: &'a IpcResponse,
+}
+
+/// A message that failed to parse. `request_id` is kept whenever the JSON
+/// itself was readable, so the caller's promise still settles.
+#[derive(Debug)]
+pub struct MalformedRequest {
+    pub request_id: Option<u64>,
+    pub error: serde_json::Error,
+}
+
+impl std::fmt::Display for MalformedRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+pub fn parse_request(raw: &str) -> Result<IpcRequest, MalformedRequest> {
+    let value: serde_json::Value = serde_json::from_str(raw)
+        .map_err(|error| MalformedRequest { request_id: None, error })?;
+    let request_id = value.get("request_id").and_then(serde_json::Value::as_u64);
+    serde_json::from_value(value).map_err(|error| MalformedRequest { request_id, error })
+}
+
+/// Applies a command to the core. Every mutation answers with a fresh state
+/// snapshot so the shell never has to track tabs on its own.
+pub fn dispatch(core: &mut BrowserCore, command: IpcCommand) -> IpcResponse {
+    match command {
+        IpcCommand::GetState => {}
+        IpcCommand::CreateTab { url } => {
+            core.create_tab(url.as_deref().unwrap_or(""));
+        }
+        IpcCommand::CloseTab { tab_id } => {
+            core.close_tab(tab_id);
+            if core.get_tabs().is_empty() {
+                core.create_tab("");
+            }
+        }
+        IpcCommand::SwitchTab { tab_id } => {
+            if !core.switch_tab(tab_id) {
+                return error(format!("No tab with id {}", tab_id));
+            }
+        }
+        IpcCommand::Navigate { tab_id, url } => {
+            let target = tab_id.or_else(|| core.get_active_tab().map(|t| t.id));
+            match target {
+                Some(id) if core.navigate(id, &url).is_some() => {}
+                _ => {
+                    core.create_tab(&url);
+                }
+            }
+        }
+        IpcCommand::PageLoaded { tab_id, url, title, user_initiated } => {
+            core.update_tab_from_page(tab_id, &url, title.as_deref(), user_initiated);
+        }
+        IpcCommand::Go { tab_id, delta } => {
+            let target = tab_id.or_else(|| core.get_active_tab().map(|t| t.id));
+            if target.and_then(|id| core.go(id, delta)).is_none() {
+                return error(format!("Cannot go {} in history", delta));
+            }
+        }
+        IpcCommand::SetScroll { tab_id, x, y } => core.set_scroll_position(tab_id, x, y),
+        IpcCommand::DiscardTab { tab_id } => {
+            if !core.discard_tab(tab_id) {
+                return error(format!("Cannot discard tab {}", tab_id));
+            }
+        }
+        IpcCommand::CollectGarbage => {}
+        IpcCommand::ToggleBooster => core.toggle_booster_mode(),
+        IpcCommand::SetBooster { enabled } => core.set_booster_enabled(enabled),
+        IpcCommand::ToggleUltimateBoost => core.toggle_ultimate_boost(),
+        IpcCommand::SetUltimateBoost { enabled } => core.set_ultimate_boost_enabled(enabled),
+        IpcCommand::SetFlashEnabled { enabled } => core.set_flash_enabled(enabled),
+        IpcCommand::GetMemoryStats => return IpcResponse::MemoryStats(core.get_memory_stats()),
+        IpcCommand::GetInjectionScript => {
+            return IpcResponse::Injection { script: injection::compose(core) };
+        }
+        IpcCommand::GetSettings => return IpcResponse::Settings(core.get_settings().clone()),
+        IpcCommand::SetRestoreSession { enabled } => {
+            core.set_restore_session(enabled);
+            return IpcResponse::Settings(core.get_settings().clone());
+        }
+        IpcCommand::GetRecoveredTabs => {
+            return IpcResponse::RecoveredTabs { tabs: core.get_recovered_tabs() };
+        }
+        IpcCommand::RestoreTabs { tab_ids } => {
+            core.restore_recovered_tabs(&tab_ids);
+        }
+        IpcCommand::DiscardRecovered => core.discard_recovered_session(),
+        IpcCommand::GetBookmarks => return IpcResponse::Bookmarks(core.get_bookmarks().clone()),
+        IpcCommand::AddBookmark { url, title, folder_id, tags } => {
+            return bookmarks_reply(core, |b| b.add_bookmark(&url, &title, folder_id, tags).map(|_| ()));
+        }
+        IpcCommand::UpdateBookmark { id, update } => {
+            return bookmarks_reply(core, |b| b.update_bookmark(id, update));
+        }
+        IpcCommand::MoveBookmark { id, folder_id } => {
+            return bookmarks_reply(core, |b| b.move_bookmark(id, folder_id));
+        }
+        IpcCommand::DeleteBookmark { id } => {
+            return bookmarks_reply(core, |b| b.remove_bookmark(id));
+        }
+        IpcCommand::ToggleBookmark { tab_id } => {
+            let Some(tab) = core.get_tabs().into_iter().find(|t| t.id == tab_id).cloned() else {
+                return error(format!("No tab with id {}", tab_id));
+            };
+            let existing = core.get_bookmarks().find_by_url(&tab.url).map(|b| b.id);
+            let result = core.edit_bookmarks(|b| match existing {
+                Some(id) => b.remove_bookmark(id),
+                None => b.add_bookmark(&tab.url, &tab.title, None, Vec::new()).map(|_| ()),
+            });
+            if let Err(e) = result {
+                return error(e.to_string());
+            }
+        }
+        IpcCommand::AddBookmarkFolder { name, parent_id } => {
+            return bookmarks_reply(core, |b| b.add_folder(&name, parent_id).map(|_| ()));
+        }
+        IpcCommand::RenameBookmarkFolder { id, name } => {
+            return bookmarks_reply(core, |b| b.rename_folder(id, &name));
+        }
+        IpcCommand::MoveBookmarkFolder { id, parent_id } => {
+            return bookmarks_reply(core, |b| b.move_folder(id, parent_id));
+        }
+        IpcCommand::DeleteBookmarkFolder { id } => {
+            return bookmarks_reply(core, |b| b.remove_folder(id));
+        }
+        IpcCommand::ImportBookmarks { content } => {
+            return match core.edit_bookmarks(|b| bookmark_transfer::import(b, &content)) {
+                Ok(summary) => IpcResponse::BookmarkImport {
+                    summary,
+                    store: core.get_bookmarks().clone(),
+                },
+                Err(e) => error(e.to_string()),
+            };
+        }
+        IpcCommand::ExportBookmarks => {
+            let store = core.get_bookmarks();
+            let path = match bookmark_transfer::export_to_file(store) {
+                Ok(path) => Some(path.display().to_string()),
+                Err(e) => {
+                    log::warn!("Failed to write bookmark export: {}", e);
+                    None
+                }
+            };
+            return IpcResponse::BookmarkExport {
+                html: bookmark_transfer::export_netscape(store),
+                path,
+            };
+        }
+        IpcCommand::ReloadTab { tab_id } => {
+            if core.reload(tab_id).is_none() {
+                return error(format!("No tab with id {}", tab_id));
+            }
+            return IpcResponse::ReloadTab { tab_id };
+        }
+        IpcCommand::GetHistory { query, limit } => {
+            return IpcResponse::History {
+                visits: core.search_history(&query, limit.unwrap_or(DEFAULT_HISTORY_LIMIT)),
+            };
+        }
+        IpcCommand::DeleteHistoryVisit { id } => {
+            if !core.delete_history_visit(id) {
+                return error(format!("No history entry with id {}", id));
+            }
+            return IpcResponse::History { visits: core.search_history("", DEFAULT_HISTORY_LIMIT) };
+        }
+        IpcCommand::DeleteHistoryRange { from, to } => {
+            core.delete_history_range(from, to);
+            return IpcResponse::History { visits: core.search_history("", DEFAULT_HISTORY_LIMIT) };
+        }
+        IpcCommand::IndexPage { tab_id, origin, text } => {
+            core.index_page_content(tab_id, &origin, &text);
+        }
+        IpcCommand::SearchContent { query, since, limit } => {
+            return IpcResponse::ContentResults {
+                results: core.search_content(&query, since, limit.unwrap_or(DEFAULT_CONTENT_RESULTS)),
+            };
+        }
+        IpcCommand::SetIndexPageContent { enabled } => {
+            core.set_index_page_content(enabled);
+            return IpcResponse::Settings(core.get_settings().clone());
+        }
+        IpcCommand::SetCleanUrls { enabled } => {
+            core.set_clean_urls(enabled);
+            return IpcResponse::Settings(core.get_settings().clone());
+        }
+        IpcCommand::SetHttpsOnly { enabled } => {
+            core.set_https_only(enabled);
+            return IpcResponse::Settings(core.get_settings().clone());
+        }
+        IpcCommand::SetDiscardAfter { minutes } => {
+            core.set_discard_after_mins(minutes);
+            return IpcResponse::Settings(core.get_settings().clone());
+        }
+        IpcCommand::SetAutoUltimateBoost { enabled } => {
+            core.set_auto_ultimate_boost(enabled);
+            return IpcResponse::Settings(core.get_settings().clone());
+        }
+        IpcCommand::RemoveHttpException { host } => {
+            core.remove_http_exception(&host);
+            return IpcResponse::Settings(core.get_settings().clone());
+        }
+        IpcCommand::HttpsUpgradeFailed { tab_id, url } => {
+            // Stale if the tab has moved on since the probe started.
+            core.https_upgrade_failed(tab_id, &url);
+        }
+        IpcCommand::ContinueOverHttp { tab_id, url, always } => {
+            let target = tab_id.or_else(|| core.get_active_tab().map(|t| t.id));
+            if target.and_then(|id| core.continue_over_http(id, &url, always)).is_none() {
+                return error(format!("Cannot load {}", url));
+            }
+        }
+        IpcCommand::ClearContentIndex => {
+            core.clear_content_index();
+            return IpcResponse::ContentResults { results: Vec::new() };
+        }
+        IpcCommand::GetSuggestions { input, limit } => {
+            let suggestions = omnibox::suggest(core, &input, limit.unwrap_or(DEFAULT_SUGGESTIONS));
+            let remote = omnibox::remote_suggest(core, &input);
+            return IpcResponse::Suggestions { input, suggestions, remote };
+        }
+        IpcCommand::AddSearchEngine { engine } => {
+            return search_engines_reply(core, |r| r.add(engine));
+        }
+        IpcCommand::RemoveSearchEngine { keyword } => {
+            return search_engines_reply(core, |r| r.remove(&keyword));
+        }
+        IpcCommand::SetDefaultSearchEngine { keyword } => {
+            return search_engines_reply(core, |r| r.set_default(&keyword));
+        }
+        IpcCommand::DiscoverSearchEngine { tab_id, origin, descriptor_url, xml } => {
+            if let Err(e) = core.discover_search_engine(tab_id, &origin, &descriptor_url, &xml) {
+                log::warn!("Ignoring OpenSearch descriptor {}: {}", descriptor_url, e);
+            }
+        }
+        IpcCommand::GetDiscoveredSearchEngines => {
+            return IpcResponse::DiscoveredSearchEngines {
+                engines: core.get_discovered_engines().to_vec(),
+            };
+        }
+        IpcCommand::InstallSearchEngine { descriptor_url, keyword } => {
+            return match core.install_discovered_engine(&descriptor_url, keyword.as_deref()) {
+                Ok(()) => IpcResponse::Settings(core.get_settings().clone()),
+                Err(e) => error(e.to_string()),
+            };
+        }
+        IpcCommand::DismissSearchEngine { descriptor_url } => {
+            core.dismiss_discovered_engine(&descriptor_url);
+        }
+        IpcCommand::GetBangs => return IpcResponse::Bangs { bangs: core.get_bangs().entries() },
+        IpcCommand::SetBang { bang } => return bangs_reply(core, |t| t.set(bang)),
+        IpcCommand::RemoveBang { trigger } => return bangs_reply(core, |t| t.remove(&trigger)),
+        IpcCommand::GetRedirects => {
+            return IpcResponse::Redirects { rules: core.get_settings().redirects.rules().to_vec() };
+        }
+        IpcCommand::SaveRedirect { rule } => return redirects_reply(core, |r| r.save(rule).map(|_| ())),
+        IpcCommand::RemoveRedirect { id } => return redirects_reply(core, |r| r.remove(id)),
+        IpcCommand::SetRedirectEnabled { id, enabled } => {
+            return redirects_reply(core, |r| r.set_enabled(id, enabled));
+        }
+        IpcCommand::TestRedirect { url, rule } => {
+            let Ok(url) = Url::parse(url.trim()) else {
+                return error("Enter a full URL, including https://");
+            };
+            let result = match rule {
+                Some(rule) => rule.rewrite(url.as_str()).map(|target| target.map(|t| (t, rule.name))),
+                None => Ok(core.get_settings().redirects.apply(&url).map(|(t, r)| (t, r.name.clone()))),
+            };
+            return match result {
+                Ok(Some((target, name))) => IpcResponse::RedirectTest { url: Some(target.into()), rule: Some(name) },
+                Ok(None) => IpcResponse::RedirectTest { url: None, rule: None },
+                Err(e) => error(e.to_string()),
+            };
+        }
+    }
+
+    IpcResponse::State(core.snapshot())
+}
+
+fn bookmarks_reply(
+    core: &mut BrowserCore,
+    edit: impl FnOnce(&mut BookmarkStore) -> Result<(), BookmarkError>,
+) -> IpcResponse {
+    match core.edit_bookmarks(edit) {
+        Ok(()) => IpcResponse::Bookmarks(core.get_bookmarks().clone()),
+        Err(e) => error(e.to_string()),
+    }
+}
+
+fn search_engines_reply(
+    core: &mut BrowserCore,
+    edit: impl FnOnce(&mut SearchEngineRegistry) -> Result<(), SearchEngineError>,
+) -> IpcResponse {
+    match core.edit_search_engines(edit) {
+        Ok(()) => IpcResponse::Settings(core.get_settings().clone()),
+        Err(e) => error(e.to_string()),
+    }
+}
+
+fn bangs_reply(
+    core: &mut BrowserCore,
+    edit: impl FnOnce(&mut BangTable) -> Result<(), BangError>,
+) -> IpcResponse {
+    match core.edit_bangs(edit) {
+        Ok(()) => IpcResponse::Bangs { bangs: core.get_bangs().entries() },
+        Err(e) => error(e.to_string()),
+    }
+}
+
+fn redirects_reply(
+    core: &mut BrowserCore,
+    edit: impl FnOnce(&mut RedirectRules) -> Result<(), RedirectError>,
+) -> IpcResponse {
+    match core.edit_redirects(edit) {
+        Ok(()) => IpcResponse::Redirects { rules: core.get_settings().redirects.rules().to_vec() },
+        Err(e) => error(e.to_string()),
+    }
+}
+
+pub fn error(message: impl Into<String>) -> IpcResponse {
+    IpcResponse::Error { message: message.into() }
+}
+
+/// JavaScript that hands a reply (or an unsolicited push with no request id) to the shell.
+pub fn reply_script(request_id: Option<u64>, response: &IpcResponse) -> String {
+    let json = serde_json::to_string(&IpcReply { request_id, response })
+        .unwrap_or_else(|_| r#"{"request_id":null,"type":"error","message":"serialization failed"}"#.to_string());
+    format!("window.__minimalist && window.__minimalist.receive({});", json)
+}
@@ -2,21 +2,54 @@ use std::sync::{Arc, Mutex};
 use wry::{
     application::{
         event::{Event, StartCause, WindowEvent},
-        event_loop::{ControlFlow, EventLoop},
+        event_loop::{ControlFlow, EventLoopBuilder},
         window::WindowBuilder,
         dpi::LogicalSize,
     },
-    webview::WebViewBuilder,
+    webview::{WebView, WebViewBuilder},
 };
-use log::info;
+use wry::http::Method;
+use log::{info, warn};
 
 mod browser_core;
+mod browsing_history;
+mod api;
 mod assets;
+mod bangs;
+mod bookmark_transfer;
+mod bookmarks;
+mod content_index;
+mod injection;
+mod ipc;
+mod memory_history;
+mod memory_watchdog;
+mod navigation;
+mod omnibox;
+mod opensearch;
+mod process_memory;
+mod redirects;
+mod router;
+mod search_engines;
+mod session;
+mod settings;
+mod storage;
+mod tab_history;
+mod templates;
+mod url_cleaner;
 
 use browser_core::BrowserCore;
+use settings::Settings;
 
 const SHELL_HTML: &str = include_str!("./shell.html");
 
+/// Events delivered to the main loop from webview callbacks.
+enum UserEvent {
+    /// Script to evaluate in the shell, typically an IPC reply.
+    EvaluateScript(String),
+    /// Release WebKit's caches, from minimalist://tasks.
+    CollectGarbage,
+}
+
 fn main() -> wry::Result<()> {
     env_logger::Builder::from_env(
         env_logger::Env::default().default_filter_or("info")
@@ -25,7 +58,8 @@ fn main() -> wry::Result<()> {
     info!("Starting Minimalist Browser v1.0.0");
     setup_browser_directories().expect("Failed to setup directories");
     
-    let event_loop = EventLoop::new();
+    let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
+    let proxy = event_loop.create_proxy();
     let window = WindowBuilder::new()
         .with_title("Minimalist Browser - Ultra Lightweight Flash Browser")
         .with_inner_size(LogicalSize::new(1400.0, 900.0))
@@ -34,64 +68,118 @@ fn main() -> wry::Result<()> {
 
     let browser = Arc::new(Mutex::new(BrowserCore::new()));
     let browser_clone = browser.clone();
+    let ipc_browser = browser.clone();
     
-    // Create initial tab
+    // Restore the last session unless disabled in settings or `--fresh` is passed.
+    // Tabs from a crashed run are offered on minimalist://restore instead.
     {
         let mut b = browser.lock().unwrap();
-        b.create_tab("minimalist://newtab");
+        let settings = Settings::load();
+        let fresh = std::env::args().any(|arg| arg == "--fresh");
+        let crashed = session::begin_run();
+        let previous = session::load();
+        let recovered = session::load_recovered();
+        // Keep a crashed run's tabs on disk before the next session save
+        // overwrites them; they stay there until restored or dismissed, even
+        // with `--fresh`, which only skips the automatic restore.
+        let (previous, recovered) = if crashed {
+            let recovered = session::merge_recovered(recovered, previous);
+            session::save_recovered(recovered.as_ref());
+            (None, recovered)
+        } else {
+            (previous.filter(|_| !fresh), recovered)
+        };
+        let restore = settings.restore_session;
+        b.set_settings(settings);
+        b.set_bookmarks(bookmarks::BookmarkStore::load());
+        b.set_bangs(bangs::BangTable::load());
+        b.set_url_cleaner(url_cleaner::UrlCleaner::load());
+        b.set_browsing_history(browsing_history::BrowsingHistory::load());
+        b.set_content_index(content_index::ContentIndex::load());
+        
+        let has_recovered = recovered.is_some();
+        if let Some(recovered) = recovered {
+            warn!("Previous run did not exit cleanly; {} tab(s) recoverable", recovered.tabs.len());
+            b.set_recovered_session(recovered);
+        }
+        match previous {
+            Some(previous) if restore => {
+                info!("Restoring {} tab(s) from last session", previous.tabs.len());
+                b.restore_session(previous);
+            }
+            _ if has_recovered => {}
+            _ => {
+                b.create_tab("minimalist://newtab");
+            }
+        }
+        if has_recovered {
+            b.create_tab("minimalist://restore");
+        }
     }
 
-    let _webview = WebViewBuilder::new(window)?
+    // One worker is plenty for the memory watchdog's periodic sampling. The
+    // runtime lives until exit since `event_loop.run` never returns.
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_time()
+        .build()
+        .expect("Failed to start background runtime");
+    let watchdog_proxy = proxy.clone();
+    memory_watchdog::spawn(&runtime, browser.clone(), move |script| {
+        let _ = watchdog_proxy.send_event(UserEvent::EvaluateScript(script));
+    });
+
+    let router = router::Router::with_builtin_pages();
+    let protocol_proxy = proxy.clone();
+    
+    let builder = WebViewBuilder::new(window)?
         .with_html(SHELL_HTML)?
         .with_custom_protocol("minimalist".into(), move |request| {
-            let path = request.uri().path();
-            let _browser = browser_clone.lock().unwrap();
-            
-            let content = match path {
-                "/newtab" => {
-                    if let Some(page) = assets::browser_pages::BROWSER_PAGES.get("newtab") {
-                        page.content.as_bytes().to_vec()
-                    } else {
-                        b"Page not found".to_vec()
-                    }
+            let mutation = request.method() == Method::POST;
+            let settings_changed = mutation && request.uri().path() == "/api/settings";
+            let mut browser = browser_clone.lock().unwrap();
+            let response = router.handle(request, &mut browser);
+            // API mutations bypass the IPC channel, so tell the shell what changed.
+            if mutation && response.status().is_success() {
+                session::save(&browser);
+                let mut script = ipc::reply_script(None, &ipc::IpcResponse::State(browser.snapshot()));
+                if settings_changed {
+                    let settings = ipc::IpcResponse::Settings(browser.get_settings().clone());
+                    script.push_str(&ipc::reply_script(None, &settings));
                 }
-                "/settings" => {
-                    if let Some(page) = assets::browser_pages::BROWSER_PAGES.get("settings") {
-                        page.content.as_bytes().to_vec()
-                    } else {
-                        b"Page not found".to_vec()
-                    }
-                }
-                "/memory" => {
-                    if let Some(page) = assets::browser_pages::BROWSER_PAGES.get("memory") {
-                        page.content.as_bytes().to_vec()
-                    } else {
-                        b"Page not found".to_vec()
+                let _ = protocol_proxy.send_event(UserEvent::EvaluateScript(script));
+            }
+            response
+        })
+        .with_ipc_handler(move |_window, message| {
+            let script = match ipc::parse_request(&message) {
+                Ok(request) => {
+                    let mut browser = ipc_browser.lock().unwrap();
+                    let persist = request.command.changes_session();
+                    let collect = matches!(request.command, ipc::IpcCommand::CollectGarbage);
+                    let response = ipc::dispatch(&mut browser, request.command);
+                    if persist {
+                        session::save(&browser);
                     }
-                }
-                "/flash" => {
-                    if let Some(page) = assets::browser_pages::BROWSER_PAGES.get("flash") {
-                        page.content.as_bytes().to_vec()
-                    } else {
-                        b"Page not found".to_vec()
+                    if collect {
+                        let _ = proxy.send_event(UserEvent::CollectGarbage);
                     }
+                    ipc::reply_script(request.request_id, &response)
                 }
-                _ => {
-                    if let Some(page) = assets::browser_pages::BROWSER_PAGES.get("error") {
-                        page.content.as_bytes().to_vec()
-                    } else {
-                        b"Page not found".to_vec()
-                    }
+                Err(e) => {
+                    warn!("Malformed IPC message: {}", e);
+                    ipc::reply_script(e.request_id, &ipc::error(e.to_string()))
                 }
             };
-            
-            wry::http::Response::builder()
-                .header("Content-Type", "text/html; charset=utf-8")
-                .body(content.into())
-                .unwrap()
+            let _ = proxy.send_event(UserEvent::EvaluateScript(script));
         })
-        .with_devtools(true)
-        .build()?;
+        .with_devtools(true);
+    // WebKitGTK gets the bootstrap from register_frame_bootstrap instead.
+    #[cfg(not(target_os = "linux"))]
+    let builder = builder.with_initialization_script(injection::FRAME_BOOTSTRAP);
+    let webview = builder.build()?;
+    #[cfg(target_os = "linux")]
+    register_frame_bootstrap(&webview);
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Wait;
@@ -100,11 +188,20 @@ fn main() -> wry::Result<()> {
             Event::NewEvents(StartCause::Init) => {
                 info!("Browser window initialized successfully");
             }
+            Event::UserEvent(UserEvent::EvaluateScript(script)) => {
+                if let Err(e) = webview.evaluate_script(&script) {
+                    warn!("Failed to deliver script to shell: {}", e);
+                }
+            }
+            Event::UserEvent(UserEvent::CollectGarbage) => collect_garbage(&webview),
             Event::WindowEvent {
                 event: WindowEvent::CloseRequested,
                 ..
             } => {
                 info!("Browser closing");
+                let mut b = browser.lock().unwrap();
+                session::save(&b);
+                session::end_run();
                 *control_flow = ControlFlow::Exit;
             }
             _ => {}
@@ -112,6 +209,50 @@ fn main() -> wry::Result<()> {
     });
 }
 
+/// wry registers initialization scripts for the top frame only on WebKitGTK,
+/// where pages live in iframes, so the bootstrap goes to the user content
+/// manager directly for every frame.
+#[cfg(target_os = "linux")]
+fn register_frame_bootstrap(webview: &WebView) {
+    use webkit2gtk::{
+        UserContentInjectedFrames, UserContentManagerExt, UserScript, UserScriptInjectionTime, WebViewExt,
+    };
+    use wry::webview::WebviewExtUnix;
+    
+    let script = UserScript::new(
+        injection::FRAME_BOOTSTRAP,
+        UserContentInjectedFrames::AllFrames,
+        UserScriptInjectionTime::Start,
+        &[],
+        &[],
+    );
+    match webview.webview().user_content_manager() {
+        Some(manager) => manager.add_script(&script),
+        None => warn!("No user content manager; page scripts will only reach same-origin pages"),
+    }
+}
+
+/// WebKitGTK offers embedders no JavaScript GC trigger, so this drops the
+/// memory cache, the largest pool it will release on request.
+#[cfg(target_os = "linux")]
+fn collect_garbage(webview: &WebView) {
+    use webkit2gtk::{WebContextExt, WebViewExt};
+    use wry::webview::WebviewExtUnix;
+    
+    match webview.webview().context() {
+        Some(context) => {
+            context.clear_cache();
+            info!("Cleared WebKit memory cache");
+        }
+        None => warn!("No web context to clear"),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn collect_garbage(_webview: &WebView) {
+    info!("Releasing web content memory is only supported with WebKitGTK");
+}
+
 fn setup_browser_directories() -> std::io::Result<()> {
     let dirs = vec![
         "browser_data",
@@ -0,0 +1,92 @@
+// memory_history.rs - Recent memory samples and mode changes, for graphs and export
+use std::collections::VecDeque;
+use serde::Serialize;
+use crate::browser_core::MemoryStats;
+use crate::tab_history::now_secs;
+
+/// An hour of samples at the watchdog's five-second interval.
+const CAPACITY: usize = 720;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MemorySample {
+    /// Unix seconds.
+    pub timestamp: u64,
+    pub total_mb: usize,
+    pub limit_mb: usize,
+    /// Whole-tree figures; `None` where only this process could be measured.
+    pub rss_kb: Option<u64>,
+    pub pss_kb: Option<u64>,
+    pub uss_kb: Option<u64>,
+    pub swap_kb: Option<u64>,
+    pub booster_active: bool,
+    pub ultimate_boost_active: bool,
+}
+
+/// Something that happened between samples, e.g. booster mode switching on.
+#[derive(Serialize, Clone, Debug)]
+pub struct MemoryAnnotation {
+    pub timestamp: u64,
+    pub label: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct MemoryHistory {
+    samples: VecDeque<MemorySample>,
+    annotations: VecDeque<MemoryAnnotation>,
+}
+
+impl MemoryHistory {
+    pub fn record(&mut self, stats: &MemoryStats) {
+        let totals = stats.breakdown.as_ref().map(|b| b.totals);
+        if self.samples.len() == CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(MemorySample {
+            timestamp: now_secs(),
+            total_mb: stats.total_mb,
+            limit_mb: stats.limit_mb,
+            rss_kb: totals.map(|t| t.rss_kb),
+            pss_kb: totals.and_then(|t| t.pss_kb),
+            uss_kb: totals.and_then(|t| t.uss_kb),
+            swap_kb: totals.map(|t| t.swap_kb),
+            booster_active: stats.booster_active,
+            ultimate_boost_active: stats.ultimate_boost_active,
+        });
+
+        // Annotations from before the oldest sample can't be placed on a graph.
+        let oldest = self.samples.front().map_or(0, |s| s.timestamp);
+        while self.annotations.front().is_some_and(|a| a.timestamp < oldest) {
+            self.annotations.pop_front();
+        }
+    }
+
+    pub fn annotate(&mut self, label: impl Into<String>) {
+        if self.annotations.len() == CAPACITY {
+            self.annotations.pop_front();
+        }
+        self.annotations.push_back(MemoryAnnotation { timestamp: now_secs(), label: label.into() });
+    }
+
+    /// One row per sample; annotations are only in the JSON form.
+    pub fn to_csv(&self) -> String {
+        let optional = |kb: Option<u64>| kb.map(|kb| kb.to_string()).unwrap_or_default();
+        let mut csv = String::from(
+            "timestamp,total_mb,limit_mb,rss_kb,pss_kb,uss_kb,swap_kb,booster_active,ultimate_boost_active\n",
+        );
+        for s in &self.samples {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                s.timestamp,
+                s.total_mb,
+                s.limit_mb,
+                optional(s.rss_kb),
+                optional(s.pss_kb),
+                optional(s.uss_kb),
+                optional(s.swap_kb),
+                s.booster_active,
+                s.ultimate_boost_active,
+            ));
+        }
+        csv
+    }
+}
@@ -0,0 +1,241 @@
+// memory_watchdog.rs - Background sampler that enforces BrowserCore's memory limit
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde::Serialize;
+use crate::browser_core::{BrowserCore, MemoryStats};
+use crate::injection;
+use crate::ipc::{self, IpcResponse};
+use crate::process_memory::{self, MemoryBreakdown};
+use crate::session;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+/// Share of the limit, in percent, at which the shell is warned.
+const WARNING_PERCENT: usize = 80;
+/// Samples to wait after a mitigation before escalating again, so the
+/// previous step has a chance to show up in the numbers.
+const SETTLE_SAMPLES: u32 = 3;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PressureLevel {
+    Normal,
+    /// At or above `WARNING_PERCENT` of the limit.
+    Warning,
+    /// Over the limit; mitigations apply.
+    Critical,
+}
+
+impl PressureLevel {
+    fn of(total_mb: usize, limit_mb: usize) -> Self {
+        if total_mb > limit_mb {
+            PressureLevel::Critical
+        } else if total_mb * 100 >= limit_mb * WARNING_PERCENT {
+            PressureLevel::Warning
+        } else {
+            PressureLevel::Normal
+        }
+    }
+}
+
+/// Escalation steps, in the order they are tried. Ultimate Boost clears
+/// site storage, so it is skipped if the user turned it off in settings.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mitigation {
+    EnableBooster,
+    EnableUltimateBoost,
+    DiscardBackgroundTabs,
+}
+
+pub struct MemoryWatchdog {
+    level: PressureLevel,
+    settle: u32,
+}
+
+impl MemoryWatchdog {
+    pub fn new() -> Self {
+        Self { level: PressureLevel::Normal, settle: 0 }
+    }
+
+    /// Records one sample, applying the next mitigation if usage is over the
+    /// limit. Returns an event for the shell when the level changed or a
+    /// mitigation was applied.
+    pub fn sample(&mut self, core: &mut BrowserCore, breakdown: Option<MemoryBreakdown>) -> Option<IpcResponse> {
+        let stats = core.record_memory_sample(breakdown);
+        let level = PressureLevel::of(stats.total_mb, stats.limit_mb);
+        let changed = level != self.level;
+        self.level = level;
+
+        if changed {
+            match level {
+                PressureLevel::Normal => log::info!("Memory back under control: {} MB", stats.total_mb),
+                _ => log::warn!("Memory pressure {:?}: {} of {} MB", level, stats.total_mb, stats.limit_mb),
+            }
+        }
+
+        let mitigation = if level == PressureLevel::Critical {
+            self.escalate(core, &stats)
+        } else {
+            self.settle = 0;
+            None
+        };
+
+        (changed || mitigation.is_some()).then_some(IpcResponse::MemoryPressure {
+            level,
+            total_mb: stats.total_mb,
+            limit_mb: stats.limit_mb,
+            mitigation,
+        })
+    }
+
+    fn escalate(&mut self, core: &mut BrowserCore, stats: &MemoryStats) -> Option<Mitigation> {
+        if self.settle > 0 {
+            self.settle -= 1;
+            return None;
+        }
+        let mitigation = if !stats.booster_active {
+            core.set_booster_enabled(true);
+            Mitigation::EnableBooster
+        } else if !stats.ultimate_boost_active && core.get_settings().auto_ultimate_boost {
+            core.set_ultimate_boost_enabled(true);
+            Mitigation::EnableUltimateBoost
+        } else if core.discard_background_tabs() > 0 {
+            Mitigation::DiscardBackgroundTabs
+        } else {
+            return None;
+        };
+        log::warn!("Over the {} MB memory limit; applying {:?}", stats.limit_mb, mitigation);
+        self.settle = SETTLE_SAMPLES;
+        Some(mitigation)
+    }
+}
+
+/// Samples on `runtime` for the life of the browser, discarding idle tabs
+/// on the same tick. `deliver` receives scripts for the shell: the pressure
+/// event, a fresh state snapshot whenever tabs or modes changed, and after a
+/// mitigation the page script, so loaded pages pick up the new modes.
+pub fn spawn<F>(runtime: &tokio::runtime::Runtime, core: Arc<Mutex<BrowserCore>>, deliver: F)
+where
+    F: Fn(String) + Send + 'static,
+{
+    runtime.spawn(async move {
+        let mut watchdog = MemoryWatchdog::new();
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        loop {
+            interval.tick().await;
+            // Walking /proc is slow, so it happens before taking the lock
+            // the IPC and page handlers wait on.
+            let breakdown = process_memory::sample();
+            let script = {
+                let mut core = core.lock().unwrap();
+                let idle_discarded = core.discard_idle_tabs() > 0;
+                let event = watchdog.sample(&mut core, breakdown);
+                let mitigated = matches!(event, Some(IpcResponse::MemoryPressure { mitigation: Some(_), .. }));
+                let mut script = event.map(|e| ipc::reply_script(None, &e)).unwrap_or_default();
+                if idle_discarded || mitigated {
+                    session::save(&core);
+                    script.push_str(&ipc::reply_script(None, &IpcResponse::State(core.snapshot())));
+                }
+                if mitigated {
+                    let injection = IpcResponse::Injection { script: injection::compose(&core) };
+                    script.push_str(&ipc::reply_script(None, &injection));
+                }
+                script
+            };
+            if !script.is_empty() {
+                deliver(script);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser_core::TabData;
+    use crate::session::SessionState;
+    use crate::settings::Settings;
+    use crate::tab_history::{HistoryEntry, TabHistory};
+
+    #[test]
+    fn pressure_levels() {
+        let cases = [
+            (0, 1000, PressureLevel::Normal),
+            (799, 1000, PressureLevel::Normal),
+            (800, 1000, PressureLevel::Warning),
+            (1000, 1000, PressureLevel::Warning),
+            (1001, 1000, PressureLevel::Critical),
+            (80, 100, PressureLevel::Warning),
+            (79, 100, PressureLevel::Normal),
+            (0, 0, PressureLevel::Warning),
+            (1, 0, PressureLevel::Critical),
+        ];
+        for (total_mb, limit_mb, expected) in cases {
+            assert_eq!(PressureLevel::of(total_mb, limit_mb), expected, "{} of {} MB", total_mb, limit_mb);
+        }
+    }
+
+    /// Three loaded tabs, built from a session so nothing is written to
+    /// browsing history.
+    fn core_with_tabs(auto_ultimate_boost: bool) -> BrowserCore {
+        let tabs = ["https://a.com/", "https://b.com/", "https://c.com/"]
+            .iter()
+            .enumerate()
+            .map(|(id, url)| TabData {
+                id,
+                title: url.to_string(),
+                url: url.to_string(),
+                display_url: url.to_string(),
+                can_go_back: false,
+                can_go_forward: false,
+                history: TabHistory::new(HistoryEntry::new(url, url, url)),
+                discarded: false,
+                last_active: 0,
+            })
+            .collect();
+        let mut core = BrowserCore::new();
+        core.set_settings(Settings { auto_ultimate_boost, ..Settings::default() });
+        core.restore_session(SessionState::new(tabs, Some(0), false, false, true));
+        for id in [1, 2, 0] {
+            core.switch_tab(id);
+        }
+        core
+    }
+
+    /// What each of `samples` consecutive over-limit samples does.
+    fn escalations(core: &mut BrowserCore, samples: usize) -> Vec<Option<Mitigation>> {
+        let mut watchdog = MemoryWatchdog::new();
+        (0..samples)
+            .map(|_| {
+                let stats = core.memory_stats_from(None);
+                watchdog.escalate(core, &stats)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn escalates_in_order_and_settles_between_steps() {
+        let mut core = core_with_tabs(true);
+        let wait = [None; SETTLE_SAMPLES as usize];
+        let mut expected = vec![Some(Mitigation::EnableBooster)];
+        expected.extend(wait);
+        expected.push(Some(Mitigation::EnableUltimateBoost));
+        expected.extend(wait);
+        expected.push(Some(Mitigation::DiscardBackgroundTabs));
+        expected.extend(wait);
+        // Nothing left to try
+        expected.extend([None, None]);
+
+        assert_eq!(escalations(&mut core, expected.len()), expected);
+        let loaded = core.get_tabs().iter().filter(|t| !t.discarded).count();
+        assert_eq!(loaded, 1);
+    }
+
+    #[test]
+    fn skips_ultimate_boost_when_turned_off() {
+        let mut core = core_with_tabs(false);
+        let steps: Vec<_> = escalations(&mut core, 2 * SETTLE_SAMPLES as usize + 2).into_iter().flatten().collect();
+        assert_eq!(steps, [Mitigation::EnableBooster, Mitigation::DiscardBackgroundTabs]);
+        assert!(!core.get_memory_stats().ultimate_boost_active);
+    }
+}
@@ -0,0 +1,459 @@
+// navigation.rs - Classifies address-bar input into a typed navigation target
+use std::net::{Ipv4Addr, Ipv6Addr};
+use lazy_static::lazy_static;
+use publicsuffix::{List, Psl};
+use url::{Host, Url};
+
+lazy_static! {
+    /// The full Public Suffix List, ICANN and private sections alike. Update
+    /// it from https://publicsuffix.org/list/public_suffix_list.dat.
+    static ref PUBLIC_SUFFIXES: List = include_str!("assets/public_suffix_list.dat")
+        .parse()
+        .expect("bundled public suffix list is valid");
+}
+
+/// Schemes recognised without a `//`, so `about:blank` isn't mistaken for `host:port`.
+const OPAQUE_SCHEMES: &[&str] = &[
+    "about", "blob", "chrome", "data", "file", "javascript", "mailto", "tel", "vbscript", "view-source",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NavigationTarget {
+    /// A web URL (or `about:blank`), already canonicalized.
+    Url(Url),
+    /// Text for a search engine; `engine` is the keyword when one was typed.
+    Search { query: String, engine: Option<String> },
+    /// A `minimalist://` page, by path (`settings`, `search?q=x`).
+    Internal(String),
+    File(Url),
+    /// Looked like an address but can't be opened.
+    Invalid { input: String, reason: String },
+}
+
+impl NavigationTarget {
+    fn search(query: &str) -> Self {
+        NavigationTarget::Search { query: query.to_string(), engine: None }
+    }
+
+    fn invalid(input: &str, reason: impl Into<String>) -> Self {
+        NavigationTarget::Invalid { input: input.to_string(), reason: reason.into() }
+    }
+
+    /// Whether the input names a location rather than something to search for.
+    pub fn is_direct(&self) -> bool {
+        matches!(self, NavigationTarget::Url(_) | NavigationTarget::Internal(_) | NavigationTarget::File(_))
+    }
+}
+
+/// Classifies what the user typed. Engine keywords and bangs are resolved by
+/// `BrowserCore::process_url` before this; everything else lands here.
+pub fn classify(input: &str) -> NavigationTarget {
+    let input = input.trim();
+    if input.is_empty() {
+        return NavigationTarget::Internal("newtab".to_string());
+    }
+
+    if let Some((scheme, _)) = split_scheme(input) {
+        return classify_with_scheme(input, &scheme);
+    }
+
+    if let Some(path) = local_path(input) {
+        return match Url::parse(&path) {
+            Ok(url) => NavigationTarget::File(url),
+            Err(e) => NavigationTarget::invalid(input, format!("Invalid file path: {}", e)),
+        };
+    }
+
+    if input.contains(char::is_whitespace) || input.contains('@') {
+        return NavigationTarget::search(input);
+    }
+
+    classify_host(input)
+}
+
+/// `http://x`, `about:blank`, `minimalist://page` -> the lowercased scheme.
+fn split_scheme(input: &str) -> Option<(String, &str)> {
+    let (scheme, rest) = input.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid {
+        return None;
+    }
+    let scheme = scheme.to_ascii_lowercase();
+    if rest.starts_with("//") || OPAQUE_SCHEMES.contains(&scheme.as_str()) || scheme == "minimalist" {
+        Some((scheme, rest))
+    } else {
+        None
+    }
+}
+
+fn classify_with_scheme(input: &str, scheme: &str) -> NavigationTarget {
+    match scheme {
+        "minimalist" => {
+            let page = input[scheme.len() + 1..].trim_start_matches('/');
+            let page = if page.is_empty() { "newtab" } else { page };
+            NavigationTarget::Internal(page.to_string())
+        }
+        "http" | "https" => match Url::parse(input) {
+            Ok(url) if url.host_str().is_some_and(|h| !h.is_empty()) => NavigationTarget::Url(url),
+            Ok(_) => NavigationTarget::invalid(input, "URL has no host"),
+            Err(e) => NavigationTarget::invalid(input, format!("Invalid URL: {}", e)),
+        },
+        "file" => match Url::parse(input) {
+            Ok(url) => NavigationTarget::File(url),
+            Err(e) => NavigationTarget::invalid(input, format!("Invalid file URL: {}", e)),
+        },
+        "about" if input.eq_ignore_ascii_case("about:blank") => {
+            NavigationTarget::Url(Url::parse("about:blank").unwrap())
+        }
+        "javascript" | "data" | "vbscript" => {
+            NavigationTarget::invalid(input, format!("{}: URLs can't be opened from the address bar", scheme))
+        }
+        _ => NavigationTarget::invalid(input, format!("Unsupported URL scheme '{}:'", scheme)),
+    }
+}
+
+/// `/home/me/page.html` or `C:\pages\index.html` as a `file://` URL string.
+fn local_path(input: &str) -> Option<String> {
+    if input.starts_with('/') && !input.starts_with("//") {
+        return Some(format!("file://{}", input));
+    }
+    let bytes = input.as_bytes();
+    let is_drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/');
+    is_drive.then(|| format!("file:///{}", input.replace('\\', "/")))
+}
+
+/// Host-like input without a scheme: `example.com/path`, `[::1]:8080`, `intranet:3000`.
+fn classify_host(input: &str) -> NavigationTarget {
+    let authority_end = input.find(['/', '?', '#']).unwrap_or(input.len());
+    let (authority, rest) = input.split_at(authority_end);
+    if authority.is_empty() {
+        return NavigationTarget::search(input);
+    }
+
+    // Bare IPv6 (`::1`, `fe80::1`) has no room for a port or path.
+    if rest.is_empty() && !authority.starts_with('[') && authority.matches(':').count() >= 2 {
+        return match authority.parse::<Ipv6Addr>() {
+            Ok(ip) => build_url(input, "http", &format!("[{}]", ip), None, ""),
+            Err(_) => NavigationTarget::search(input),
+        };
+    }
+
+    let (host, port) = match split_port(authority) {
+        Ok(Some(parts)) => parts,
+        Ok(None) => return NavigationTarget::search(input),
+        Err(reason) => return NavigationTarget::invalid(input, reason),
+    };
+
+    if let Some(inner) = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        return match inner.parse::<Ipv6Addr>() {
+            Ok(_) => build_url(input, "http", host, port, rest),
+            Err(_) => NavigationTarget::invalid(input, "Invalid IPv6 address"),
+        };
+    }
+
+    let labels: Vec<&str> = host.trim_end_matches('.').split('.').collect();
+    if labels.iter().all(|l| !l.is_empty() && l.chars().all(|c| c.is_ascii_digit())) {
+        // Numbers with dots: an IPv4 address, or just a number like 3.14.
+        return match host.parse::<Ipv4Addr>() {
+            Ok(_) => build_url(input, "http", host, port, rest),
+            Err(_) if labels.len() == 4 => NavigationTarget::invalid(input, "Invalid IPv4 address"),
+            Err(_) => NavigationTarget::search(input),
+        };
+    }
+
+    let domain = match Host::parse(host) {
+        Ok(Host::Domain(domain)) => domain,
+        Ok(_) => return NavigationTarget::search(input),
+        Err(_) if host.to_ascii_lowercase().contains("xn--") => {
+            return NavigationTarget::invalid(input, "Invalid internationalized domain name");
+        }
+        Err(_) => return NavigationTarget::search(input),
+    };
+    let domain = domain.trim_end_matches('.');
+    let labels: Vec<&str> = domain.split('.').collect();
+    if !labels.iter().all(|l| is_valid_label(l)) {
+        return NavigationTarget::search(input);
+    }
+
+    if domain == "localhost" || domain.ends_with(".localhost") {
+        return build_url(input, "http", host, port, rest);
+    }
+
+    if labels.len() == 1 {
+        // Single-label intranet hosts need a port or path to count as an address.
+        return if port.is_some() || !rest.is_empty() {
+            build_url(input, "http", host, port, rest)
+        } else {
+            NavigationTarget::search(input)
+        };
+    }
+
+    if !has_known_tld(domain) || is_public_suffix(domain) {
+        return NavigationTarget::search(input);
+    }
+    build_url(input, "https", host, port, rest)
+}
+
+/// `Ok(Some((host, port)))`, `Ok(None)` when the colon isn't a port
+/// separator (`note:to self`), or `Err` for an out-of-range port.
+fn split_port(authority: &str) -> Result<Option<(&str, Option<u16>)>, String> {
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => match bracketed.find(']') {
+            Some(end) => {
+                let host = &authority[..end + 2];
+                match &authority[end + 2..] {
+                    "" => (host, None),
+                    rest => match rest.strip_prefix(':') {
+                        Some(port) => (host, Some(port)),
+                        None => return Ok(None),
+                    },
+                }
+            }
+            None => return Err("Unterminated IPv6 address".to_string()),
+        },
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+
+    let Some(port) = port else {
+        return Ok(Some((host, None)));
+    };
+    if port.is_empty() || !port.chars().all(|c| c.is_ascii_digit()) || host.is_empty() {
+        return Ok(None);
+    }
+    match port.parse::<u16>() {
+        Ok(port) => Ok(Some((host, Some(port)))),
+        Err(_) => Err(format!("Port {} is out of range", port)),
+    }
+}
+
+fn build_url(input: &str, scheme: &str, host: &str, port: Option<u16>, rest: &str) -> NavigationTarget {
+    let port = port.map(|p| format!(":{}", p)).unwrap_or_default();
+    match Url::parse(&format!("{}://{}{}{}", scheme, host, port, rest)) {
+        Ok(url) => NavigationTarget::Url(url),
+        Err(e) => NavigationTarget::invalid(input, format!("Invalid URL: {}", e)),
+    }
+}
+
+fn is_valid_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 63
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn has_known_tld(domain: &str) -> bool {
+    let tld = domain.trim_end_matches('.').rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    PUBLIC_SUFFIXES.suffix(tld.as_bytes()).is_some_and(|suffix| suffix.is_known())
+}
+
+/// Whether `domain` is itself a public suffix like `com`, `co.uk` or
+/// `github.io`, which can't be a site.
+pub fn is_public_suffix(domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    PUBLIC_SUFFIXES
+        .suffix(domain.as_bytes())
+        .is_some_and(|suffix| suffix.is_known() && suffix.as_bytes() == domain.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(input: &str) -> String {
+        match classify(input) {
+            NavigationTarget::Url(url) => url.to_string(),
+            other => panic!("{:?} should be a URL, got {:?}", input, other),
+        }
+    }
+
+    fn is_search(input: &str) -> bool {
+        matches!(classify(input), NavigationTarget::Search { .. })
+    }
+
+    fn is_invalid(input: &str) -> bool {
+        matches!(classify(input), NavigationTarget::Invalid { .. })
+    }
+
+    #[test]
+    fn urls() {
+        let cases = [
+            // Explicit schemes
+            ("https://example.com", "https://example.com/"),
+            ("http://example.com/a?b=c#d", "http://example.com/a?b=c#d"),
+            ("HTTPS://Example.COM/Path", "https://example.com/Path"),
+            ("https://intranet", "https://intranet/"),
+            ("about:blank", "about:blank"),
+            // Domains get https
+            ("example.com", "https://example.com/"),
+            ("www.rust-lang.org/learn", "https://www.rust-lang.org/learn"),
+            ("docs.rs/serde?search=x", "https://docs.rs/serde?search=x"),
+            ("example.com.", "https://example.com./"),
+            ("sub.domain.example.co.uk", "https://sub.domain.example.co.uk/"),
+            ("bbc.co.uk", "https://bbc.co.uk/"),
+            ("example.io", "https://example.io/"),
+            ("hello.world", "https://hello.world/"),
+            ("release.zip", "https://release.zip/"),
+            ("octocat.github.io", "https://octocat.github.io/"),
+            ("my_host.example.com", "https://my_host.example.com/"),
+            ("example.com:8443/admin", "https://example.com:8443/admin"),
+            ("example.com#top", "https://example.com/#top"),
+            // IDN and punycode
+            ("münchen.de", "https://xn--mnchen-3ya.de/"),
+            ("xn--mnchen-3ya.de", "https://xn--mnchen-3ya.de/"),
+            ("пример.рф", "https://xn--e1afmkfd.xn--p1ai/"),
+            ("例え.jp/パス", "https://xn--r8jz45g.jp/%E3%83%91%E3%82%B9"),
+            // IPv4
+            ("127.0.0.1", "http://127.0.0.1/"),
+            ("192.168.1.10", "http://192.168.1.10/"),
+            ("10.0.0.1:8080/status", "http://10.0.0.1:8080/status"),
+            // IPv6
+            ("[::1]", "http://[::1]/"),
+            ("[::1]:3000", "http://[::1]:3000/"),
+            ("[2001:db8::1]/path", "http://[2001:db8::1]/path"),
+            ("::1", "http://[::1]/"),
+            ("fe80::1", "http://[fe80::1]/"),
+            // localhost and intranet hosts
+            ("localhost", "http://localhost/"),
+            ("localhost:3000", "http://localhost:3000/"),
+            ("app.localhost", "http://app.localhost/"),
+            ("intranet:8080", "http://intranet:8080/"),
+            ("wiki/Main_Page", "http://wiki/Main_Page"),
+            ("build-server:9000/jobs", "http://build-server:9000/jobs"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(url(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn searches() {
+        let cases = [
+            "rust lifetimes",
+            "hello",
+            "intranet",
+            "3.14",
+            "1.2.3",
+            "what is 2+2",
+            "file.txt",
+            "main.rs.bak",
+            "notatld.invalidtld",
+            "com",
+            "co.uk",
+            "github.io",
+            "foo.123",
+            "-bad.com",
+            "bad-.com",
+            "user@example.com",
+            "note:to self",
+            "note:",
+            "example.com:",
+            "C++",
+            "?",
+            "a..b.com",
+            "how.to example",
+        ];
+        for input in cases {
+            assert!(is_search(input), "{:?} should be a search, got {:?}", input, classify(input));
+        }
+    }
+
+    #[test]
+    fn internal_pages() {
+        let cases = [
+            ("", "newtab"),
+            ("   ", "newtab"),
+            ("minimalist://settings", "settings"),
+            ("minimalist:///history", "history"),
+            ("MINIMALIST://memory", "memory"),
+            ("minimalist://search?q=rust", "search?q=rust"),
+            ("minimalist://", "newtab"),
+        ];
+        for (input, page) in cases {
+            assert_eq!(classify(input), NavigationTarget::Internal(page.to_string()), "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn files() {
+        let cases = [
+            ("file:///home/me/index.html", "file:///home/me/index.html"),
+            ("/home/me/index.html", "file:///home/me/index.html"),
+            ("/tmp", "file:///tmp"),
+            ("C:\\pages\\index.html", "file:///C:/pages/index.html"),
+            ("d:/docs/readme.html", "file:///d:/docs/readme.html"),
+        ];
+        for (input, expected) in cases {
+            match classify(input) {
+                NavigationTarget::File(url) => assert_eq!(url.as_str(), expected, "input: {:?}", input),
+                other => panic!("{:?} should be a file, got {:?}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let cases = [
+            "javascript:alert(1)",
+            "data:text/html,<h1>hi</h1>",
+            "vbscript:msgbox",
+            "ftp://example.com",
+            "chrome://settings",
+            "about:config",
+            "mailto:someone@example.com",
+            "http://",
+            "https://exa mple.com",
+            "http://[::1",
+            "example.com:99999",
+            "localhost:70000",
+            "[::zz]",
+            "999.1.1.1",
+            "1.2.3.256",
+            "[::1",
+        ];
+        for input in cases {
+            assert!(is_invalid(input), "{:?} should be invalid, got {:?}", input, classify(input));
+        }
+    }
+
+    #[test]
+    fn public_suffixes() {
+        assert!(is_public_suffix("com"));
+        assert!(is_public_suffix("co.uk"));
+        assert!(is_public_suffix("CO.UK."));
+        assert!(is_public_suffix("de"));
+        assert!(is_public_suffix("xn--p1ai"));
+        assert!(is_public_suffix("zip"));
+        assert!(is_public_suffix("mov"));
+        assert!(is_public_suffix("bank"));
+        // Private suffixes count too
+        assert!(is_public_suffix("github.io"));
+        assert!(is_public_suffix("blogspot.com"));
+        // Wildcard and exception rules (*.ck, !www.ck)
+        assert!(is_public_suffix("anything.ck"));
+        assert!(!is_public_suffix("www.ck"));
+        // Unicode rules match their punycode form
+        assert!(is_public_suffix("xn--55qx5d.cn"));
+        assert!(!is_public_suffix("example.com"));
+        assert!(!is_public_suffix("bbc.co.uk"));
+        assert!(!is_public_suffix("user.github.io"));
+        assert!(!is_public_suffix("notatld"));
+    }
+
+    #[test]
+    fn direct_targets() {
+        assert!(classify("example.com").is_direct());
+        assert!(classify("minimalist://settings").is_direct());
+        assert!(classify("/tmp/a.html").is_direct());
+        assert!(!classify("rust book").is_direct());
+        assert!(!classify("javascript:void(0)").is_direct());
+    }
+}
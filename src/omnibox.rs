@@ -0,0 +1,362 @@
+// omnibox.rs - Ranked address bar completions from tabs, bookmarks, history and search
+use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use crate::browser_core::BrowserCore;
+use crate::browsing_history::{Transition, Visit};
+use crate::tab_history::now_secs;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+/// Score of the "search for what you typed" row; strong history or tab matches outrank it.
+const SEARCH_SCORE: f64 = 250.0;
+const DIRECT_SCORE: f64 = 10_000.0;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    /// The input itself, resolved to a URL.
+    Url,
+    /// The input sent to the search engine.
+    Search,
+    /// An already open tab; selecting it switches instead of navigating.
+    OpenTab,
+    Bookmark,
+    History,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub title: String,
+    pub url: String,
+    pub tab_id: Option<usize>,
+    /// Name of the engine for search rows.
+    pub engine: Option<String>,
+    pub score: f64,
+}
+
+/// Where the shell can fetch live search suggestions for the current input.
+#[derive(Serialize, Clone, Debug)]
+pub struct RemoteSuggest {
+    pub url: String,
+    /// Prepended to a chosen suggestion so it goes to the same engine (e.g. `"w "`).
+    pub prefix: String,
+}
+
+/// Completions for partially typed `input`, best first, one per URL.
+pub fn suggest(core: &BrowserCore, input: &str, limit: usize) -> Vec<Suggestion> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Vec::new();
+    }
+    let tokens: Vec<String> = input.to_lowercase().split_whitespace().map(String::from).collect();
+    let frecency = frecency_by_url(core.get_browsing_history().visits(), now_secs());
+    let mut candidates: Vec<Suggestion> = Vec::new();
+
+    if let Some((bang, query)) = core.get_bangs().resolve(input) {
+        return vec![Suggestion {
+            kind: SuggestionKind::Search,
+            title: if query.is_empty() { bang.name.clone() } else { query.clone() },
+            url: bang.url_for(&query),
+            tab_id: None,
+            engine: Some(bang.name.clone()),
+            score: DIRECT_SCORE,
+        }];
+    }
+    if let Some((engine, query)) = core.keyword_search(input) {
+        // An explicit engine keyword always wins; there's nothing else to complete.
+        return vec![Suggestion {
+            kind: SuggestionKind::Search,
+            title: query.to_string(),
+            url: engine.search_url(query),
+            tab_id: None,
+            engine: Some(engine.name.clone()),
+            score: DIRECT_SCORE,
+        }];
+    }
+
+    candidates.push(if core.process_url(input).is_direct() {
+        // Keep the http URL so opening the row still gets HTTPS-only's fallback.
+        let location = core.locate(input);
+        Suggestion {
+            kind: SuggestionKind::Url,
+            title: input.to_string(),
+            url: location.upgraded_from.unwrap_or(location.url),
+            tab_id: None,
+            engine: None,
+            score: DIRECT_SCORE,
+        }
+    } else {
+        // Invalid input is offered as a search too; Enter still explains the error.
+        Suggestion {
+            kind: SuggestionKind::Search,
+            title: input.to_string(),
+            url: core.search_url(input),
+            tab_id: None,
+            engine: Some(core.default_search_engine().name.clone()),
+            score: SEARCH_SCORE,
+        }
+    });
+
+    let active_tab = core.get_active_tab().map(|t| t.id);
+    for tab in core.get_tabs() {
+        if Some(tab.id) == active_tab {
+            continue;
+        }
+        if let Some(quality) = match_quality(&tokens, &tab.url, &tab.title) {
+            candidates.push(Suggestion {
+                kind: SuggestionKind::OpenTab,
+                title: tab.title.clone(),
+                url: tab.url.clone(),
+                tab_id: Some(tab.id),
+                engine: None,
+                score: tab_score(quality),
+            });
+        }
+    }
+
+    for bookmark in core.get_bookmarks().bookmarks() {
+        let tags = bookmark.tags.join(" ");
+        let haystack = format!("{} {}", bookmark.title, tags);
+        if let Some(quality) = match_quality(&tokens, &bookmark.url, &haystack) {
+            let boost = frecency.get(bookmark.url.as_str()).copied().unwrap_or(0.0);
+            candidates.push(Suggestion {
+                kind: SuggestionKind::Bookmark,
+                title: bookmark.title.clone(),
+                url: bookmark.url.clone(),
+                tab_id: None,
+                engine: None,
+                score: bookmark_score(quality, boost),
+            });
+        }
+    }
+
+    // Latest title per URL; visits are oldest first.
+    let mut titles: HashMap<&str, &str> = HashMap::new();
+    for visit in core.get_browsing_history().visits() {
+        if !visit.title.is_empty() {
+            titles.insert(&visit.url, &visit.title);
+        }
+    }
+    for (url, score) in &frecency {
+        let title = titles.get(url).copied().unwrap_or("");
+        if let Some(quality) = match_quality(&tokens, url, title) {
+            candidates.push(Suggestion {
+                kind: SuggestionKind::History,
+                title: title.to_string(),
+                url: url.to_string(),
+                tab_id: None,
+                engine: None,
+                score: history_score(quality, *score),
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(kind_rank(a.kind).cmp(&kind_rank(b.kind))));
+    let mut seen = HashSet::new();
+    candidates.retain(|s| seen.insert(s.url.trim_end_matches('/').to_string()));
+    candidates.truncate(limit);
+    candidates
+}
+
+/// The search engine's suggestion endpoint for `input`, if it is a search at all.
+pub fn remote_suggest(core: &BrowserCore, input: &str) -> Option<RemoteSuggest> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    if core.get_bangs().resolve(input).is_some() {
+        return None;
+    }
+    if let Some((engine, query)) = core.keyword_search(input) {
+        return Some(RemoteSuggest {
+            url: engine.suggest_url(query)?,
+            prefix: format!("{} ", engine.keyword),
+        });
+    }
+    if core.process_url(input).is_direct() {
+        return None;
+    }
+    Some(RemoteSuggest {
+        url: core.default_search_engine().suggest_url(input)?,
+        prefix: String::new(),
+    })
+}
+
+/// An open tab is worth switching to over reopening what history offers.
+fn tab_score(quality: f64) -> f64 {
+    300.0 * quality
+}
+
+/// An unvisited bookmark starts ahead of any URL visited just once.
+fn bookmark_score(quality: f64, frecency: f64) -> f64 {
+    100.0 * quality * (2.2 + frecency.ln_1p())
+}
+
+fn history_score(quality: f64, frecency: f64) -> f64 {
+    100.0 * quality * (1.0 + frecency.ln_1p())
+}
+
+/// When scores tie, prefer the richer action.
+fn kind_rank(kind: SuggestionKind) -> u8 {
+    match kind {
+        SuggestionKind::Url => 0,
+        SuggestionKind::OpenTab => 1,
+        SuggestionKind::Bookmark => 2,
+        SuggestionKind::History => 3,
+        SuggestionKind::Search => 4,
+    }
+}
+
+/// Frequency weighted by how recent and how deliberate each visit was.
+fn frecency_by_url(visits: &[Visit], now: u64) -> HashMap<&str, f64> {
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    for visit in visits {
+        let transition = match visit.transition {
+            Transition::Typed => 2.0,
+            Transition::Link | Transition::Redirect => 1.0,
+            Transition::BackForward => 0.5,
+            Transition::Reload => 0.0,
+        };
+        let age_days = now.saturating_sub(visit.visited_at) / DAY_SECS;
+        let recency = match age_days {
+            0..=3 => 1.0,
+            4..=13 => 0.7,
+            14..=30 => 0.5,
+            _ => 0.3,
+        };
+        *scores.entry(visit.url.as_str()).or_default() += transition * recency;
+    }
+    scores
+}
+
+/// `None` unless every token appears in the URL or title. Higher means a
+/// better match: the host starting with the input beats a word prefix,
+/// which beats a bare substring.
+fn match_quality(tokens: &[String], url: &str, title: &str) -> Option<f64> {
+    let url = url.to_lowercase();
+    let title = title.to_lowercase();
+    let bare = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+    let bare = bare.strip_prefix("www.").unwrap_or(bare);
+
+    if !tokens.iter().all(|t| bare.contains(t.as_str()) || title.contains(t.as_str())) {
+        return None;
+    }
+    if tokens.len() == 1 && bare.starts_with(tokens[0].as_str()) {
+        return Some(4.0);
+    }
+    let word_prefix = tokens.iter().all(|t| {
+        bare.split(|c: char| !c.is_alphanumeric())
+            .chain(title.split(|c: char| !c.is_alphanumeric()))
+            .any(|word| word.starts_with(t.as_str()))
+    });
+    Some(if word_prefix { 2.0 } else { 1.0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn visit(url: &str, transition: Transition, age_days: u64) -> Visit {
+        Visit {
+            id: 0,
+            url: url.to_string(),
+            title: String::new(),
+            visited_at: NOW - age_days * DAY_SECS,
+            transition,
+            tab_id: 1,
+        }
+    }
+
+    fn frecency(visits: &[Visit]) -> f64 {
+        frecency_by_url(visits, NOW).get("https://a.test/").copied().unwrap_or(0.0)
+    }
+
+    fn tokens(input: &str) -> Vec<String> {
+        input.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn match_qualities() {
+        let cases = [
+            // The host starts with the input
+            ("rust", "https://www.rust-lang.org/", "", Some(4.0)),
+            ("rust-lang.org/learn", "https://rust-lang.org/learn", "", Some(4.0)),
+            // A word in the URL or title starts with each token
+            ("lang", "https://www.rust-lang.org/", "", Some(2.0)),
+            ("rust book", "https://doc.rust-lang.org/", "The Book", Some(2.0)),
+            // Only inside words
+            ("ust", "https://www.rust-lang.org/", "", Some(1.0)),
+            ("rust ook", "https://doc.rust-lang.org/", "The Book", Some(1.0)),
+            // Every token has to match
+            ("rust python", "https://www.rust-lang.org/", "Rust", None),
+            ("https", "https://www.rust-lang.org/", "", None),
+        ];
+        for (input, url, title, expected) in cases {
+            assert_eq!(match_quality(&tokens(input), url, title), expected, "{:?} for {}", input, url);
+        }
+    }
+
+    #[test]
+    fn frecency_decays_with_age() {
+        let cases = [(0, 1.0), (3, 1.0), (4, 0.7), (13, 0.7), (14, 0.5), (30, 0.5), (31, 0.3), (89, 0.3)];
+        for (age_days, expected) in cases {
+            let visits = [visit("https://a.test/", Transition::Link, age_days)];
+            assert_eq!(frecency(&visits), expected, "{} days old", age_days);
+        }
+
+        // Deliberate visits count for more; reloads not at all.
+        let cases = [
+            (Transition::Typed, 2.0),
+            (Transition::Link, 1.0),
+            (Transition::Redirect, 1.0),
+            (Transition::BackForward, 0.5),
+            (Transition::Reload, 0.0),
+        ];
+        for (transition, expected) in cases {
+            assert_eq!(frecency(&[visit("https://a.test/", transition, 0)]), expected, "{:?}", transition);
+        }
+
+        // Visits add up per URL.
+        let visits = [
+            visit("https://a.test/", Transition::Typed, 0),
+            visit("https://a.test/", Transition::Link, 20),
+            visit("https://b.test/", Transition::Typed, 0),
+        ];
+        assert_eq!(frecency(&visits), 2.5);
+    }
+
+    #[test]
+    fn tabs_and_bookmarks_outrank_weak_history() {
+        // The most a single visit can earn: typed today.
+        let weak = frecency(&[visit("https://a.test/", Transition::Typed, 0)]);
+        for quality in [1.0, 2.0, 4.0] {
+            let history = history_score(quality, weak);
+            assert!(tab_score(quality) > history, "tab at quality {}", quality);
+            assert!(bookmark_score(quality, 0.0) > history, "bookmark at quality {}", quality);
+        }
+
+        // A better match still beats a tab or bookmark that barely matches.
+        assert!(history_score(4.0, weak) > tab_score(1.0));
+        assert!(history_score(4.0, weak) > bookmark_score(1.0, 0.0));
+
+        // History that was visited often and lately does catch up with bookmarks.
+        let frequent: Vec<Visit> = (0..5).map(|_| visit("https://a.test/", Transition::Typed, 0)).collect();
+        assert!(history_score(2.0, frecency(&frequent)) > bookmark_score(2.0, 0.0));
+    }
+
+    #[test]
+    fn ties_prefer_richer_actions() {
+        let order = [
+            SuggestionKind::Url,
+            SuggestionKind::OpenTab,
+            SuggestionKind::Bookmark,
+            SuggestionKind::History,
+            SuggestionKind::Search,
+        ];
+        for pair in order.windows(2) {
+            assert!(kind_rank(pair[0]) < kind_rank(pair[1]), "{:?} before {:?}", pair[0], pair[1]);
+        }
+    }
+}
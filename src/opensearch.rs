@@ -0,0 +1,323 @@
+// opensearch.rs - Parses OpenSearch descriptors advertised by visited pages
+use std::fmt;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use url::Url;
+use crate::bookmark_transfer::decode_entities;
+use crate::search_engines::{SearchEngine, SearchEngineRegistry, SEARCH_TERMS};
+
+/// Descriptors are a few kB; anything much bigger isn't one.
+pub const MAX_DESCRIPTOR_BYTES: usize = 64 * 1024;
+
+lazy_static! {
+    static ref SHORT_NAME: Regex = Regex::new(r"(?is)<(?:\w+:)?ShortName[^>]*>(.*?)</(?:\w+:)?ShortName>").unwrap();
+    static ref DESCRIPTION: Regex = Regex::new(r"(?is)<(?:\w+:)?Description[^>]*>(.*?)</(?:\w+:)?Description>").unwrap();
+    static ref URL_ELEMENT: Regex = Regex::new(r"(?is)<(?:\w+:)?Url\b([^>]*)>").unwrap();
+    static ref XML_ATTRIBUTE: Regex = Regex::new(r#"(?s)([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    static ref TEMPLATE_PARAM: Regex = Regex::new(r"\{([\w:]+)(\??)\}").unwrap();
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OpenSearchError {
+    NotOpenSearch,
+    MissingName,
+    NoHtmlTemplate,
+    /// The template needs a parameter we can't fill in.
+    UnsupportedParameter(String),
+    InvalidUrl(String),
+    /// The descriptor or the message isn't from the page open in the tab.
+    NotFromPage,
+    /// An http descriptor while HTTPS-Only mode is on.
+    InsecureDescriptor,
+    TooLarge,
+    /// The site already has as many offers waiting as it may.
+    TooManyOffers,
+}
+
+impl fmt::Display for OpenSearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenSearchError::NotOpenSearch => write!(f, "Not an OpenSearch description"),
+            OpenSearchError::MissingName => write!(f, "OpenSearch description has no ShortName"),
+            OpenSearchError::NoHtmlTemplate => write!(f, "OpenSearch description has no usable text/html search URL"),
+            OpenSearchError::UnsupportedParameter(p) => write!(f, "Unsupported OpenSearch parameter {{{}}}", p),
+            OpenSearchError::InvalidUrl(u) => write!(f, "Invalid search URL: {}", u),
+            OpenSearchError::NotFromPage => write!(f, "Description is not from the page's own origin"),
+            OpenSearchError::InsecureDescriptor => write!(f, "Description served over http in HTTPS-Only mode"),
+            OpenSearchError::TooLarge => write!(f, "Description is too large"),
+            OpenSearchError::TooManyOffers => write!(f, "Site has already offered too many search engines"),
+        }
+    }
+}
+
+/// An engine a page offered that hasn't been installed yet.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DiscoveredEngine {
+    pub name: String,
+    pub description: String,
+    pub url_template: String,
+    pub suggest_template: Option<String>,
+    pub descriptor_url: String,
+    pub page_url: String,
+    /// Keyword proposed when installing, derived from the site's host.
+    pub suggested_keyword: String,
+}
+
+impl DiscoveredEngine {
+    pub fn into_engine(self, keyword: &str) -> SearchEngine {
+        SearchEngine {
+            name: self.name,
+            keyword: keyword.to_string(),
+            url_template: self.url_template,
+            suggest_template: self.suggest_template,
+            descriptor_url: Some(self.descriptor_url),
+        }
+    }
+}
+
+/// Parses descriptor `xml` fetched from `descriptor_url` for the page at `page_url`.
+pub fn parse(
+    xml: &str,
+    descriptor_url: &str,
+    page_url: &str,
+    registry: &SearchEngineRegistry,
+) -> Result<DiscoveredEngine, OpenSearchError> {
+    if xml.len() > MAX_DESCRIPTOR_BYTES {
+        return Err(OpenSearchError::TooLarge);
+    }
+    if !xml.contains("OpenSearchDescription") {
+        return Err(OpenSearchError::NotOpenSearch);
+    }
+    let base = Url::parse(descriptor_url).map_err(|_| OpenSearchError::InvalidUrl(descriptor_url.to_string()))?;
+
+    let name = element_text(&SHORT_NAME, xml).ok_or(OpenSearchError::MissingName)?;
+    let description = element_text(&DESCRIPTION, xml).unwrap_or_default();
+
+    let mut url_template = None;
+    let mut suggest_template = None;
+    for captures in URL_ELEMENT.captures_iter(xml) {
+        let attributes = xml_attributes(&captures[1]);
+        let get = |key: &str| {
+            attributes.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.as_str())
+        };
+        let method = get("method").unwrap_or("get");
+        let Some(template) = get("template") else {
+            continue;
+        };
+        if !method.eq_ignore_ascii_case("get") {
+            continue;
+        }
+        match get("type") {
+            Some("text/html") if url_template.is_none() => {
+                url_template = Some(convert_template(template, &base)?);
+            }
+            Some("application/x-suggestions+json") if suggest_template.is_none() => {
+                // A broken suggestion URL shouldn't cost the user the engine itself.
+                suggest_template = convert_template(template, &base).ok();
+            }
+            _ => {}
+        }
+    }
+
+    let url_template = url_template.ok_or(OpenSearchError::NoHtmlTemplate)?;
+    let host = Url::parse(page_url).ok().and_then(|u| u.host_str().map(str::to_string));
+    Ok(DiscoveredEngine {
+        name,
+        description,
+        suggested_keyword: suggest_keyword(host.as_deref().unwrap_or(""), registry),
+        url_template,
+        suggest_template,
+        descriptor_url: descriptor_url.to_string(),
+        page_url: page_url.to_string(),
+    })
+}
+
+fn element_text(pattern: &Regex, xml: &str) -> Option<String> {
+    let text = decode_entities(pattern.captures(xml)?.get(1)?.as_str().trim());
+    (!text.is_empty()).then_some(text)
+}
+
+fn xml_attributes(raw: &str) -> Vec<(String, String)> {
+    XML_ATTRIBUTE
+        .captures_iter(raw)
+        .map(|c| {
+            let value = c.get(2).or_else(|| c.get(3)).map_or("", |m| m.as_str());
+            (c[1].to_string(), decode_entities(value))
+        })
+        .collect()
+}
+
+/// Resolves the template against the descriptor and reduces its parameters
+/// to the single `{searchTerms}` the registry fills in.
+fn convert_template(template: &str, base: &Url) -> Result<String, OpenSearchError> {
+    let mut unsupported = None;
+    let converted = TEMPLATE_PARAM.replace_all(template, |c: &regex::Captures| {
+        let name = &c[1];
+        let optional = !c[2].is_empty();
+        match name {
+            "searchTerms" => SEARCH_TERMS.to_string(),
+            "inputEncoding" | "outputEncoding" => "UTF-8".to_string(),
+            "language" => "*".to_string(),
+            "startPage" | "startIndex" if !optional => "1".to_string(),
+            _ if optional => String::new(),
+            _ => {
+                unsupported.get_or_insert_with(|| name.to_string());
+                String::new()
+            }
+        }
+    });
+    if let Some(name) = unsupported {
+        return Err(OpenSearchError::UnsupportedParameter(name));
+    }
+    if !converted.contains(SEARCH_TERMS) {
+        return Err(OpenSearchError::NoHtmlTemplate);
+    }
+
+    // Resolve with a stand-in so the braces survive URL parsing untouched.
+    let marker = "MINIMALISTSEARCHTERMS";
+    let resolved = base
+        .join(&converted.replace(SEARCH_TERMS, marker))
+        .map_err(|_| OpenSearchError::InvalidUrl(template.to_string()))?;
+    if !matches!(resolved.scheme(), "http" | "https") {
+        return Err(OpenSearchError::InvalidUrl(template.to_string()));
+    }
+    Ok(resolved.as_str().replace(marker, SEARCH_TERMS))
+}
+
+/// `docs.rs` -> `docs`, `www.github.com` -> `github`; made unique against the registry.
+pub fn suggest_keyword(host: &str, registry: &SearchEngineRegistry) -> String {
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let base: String = host
+        .split('.')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    let base = if base.is_empty() { "site".to_string() } else { base };
+
+    let mut keyword = base.clone();
+    let mut n = 2;
+    while registry.find(&keyword).is_some() {
+        keyword = format!("{}{}", base, n);
+        n += 1;
+    }
+    keyword
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTOR_URL: &str = "https://docs.rs/opensearch.xml";
+    const PAGE_URL: &str = "https://docs.rs/serde";
+
+    fn descriptor(urls: &str) -> String {
+        format!(
+            r#"<?xml version="1.0"?>
+<OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/">
+  <ShortName>Docs.rs &amp; crates</ShortName>
+  <Description>Search crates</Description>
+  {}
+</OpenSearchDescription>"#,
+            urls
+        )
+    }
+
+    fn parsed(xml: &str) -> Result<DiscoveredEngine, OpenSearchError> {
+        parse(xml, DESCRIPTOR_URL, PAGE_URL, &SearchEngineRegistry::default())
+    }
+
+    #[test]
+    fn parses_descriptors() {
+        let xml = descriptor(
+            r#"<Url type="application/x-suggestions+json" template="/suggest?q={searchTerms}"/>
+  <Url type="text/html" method="get" template="https://docs.rs/releases/search?query={searchTerms}&amp;p={startPage?}"/>"#,
+        );
+        let engine = parsed(&xml).unwrap();
+        assert_eq!(engine.name, "Docs.rs & crates");
+        assert_eq!(engine.description, "Search crates");
+        assert_eq!(engine.url_template, "https://docs.rs/releases/search?query={searchTerms}&p=");
+        assert_eq!(engine.suggest_template.as_deref(), Some("https://docs.rs/suggest?q={searchTerms}"));
+        assert_eq!(engine.suggested_keyword, "docs");
+        assert_eq!((engine.descriptor_url.as_str(), engine.page_url.as_str()), (DESCRIPTOR_URL, PAGE_URL));
+    }
+
+    #[test]
+    fn parses_prefixed_elements_and_single_quotes() {
+        let xml = r#"<os:OpenSearchDescription xmlns:os="http://a9.com/-/spec/opensearch/1.1/">
+  <os:ShortName>Example</os:ShortName>
+  <os:Url type='text/html' template='search/{searchTerms}'/>
+</os:OpenSearchDescription>"#;
+        let engine = parsed(xml).unwrap();
+        assert_eq!(engine.name, "Example");
+        assert_eq!(engine.url_template, "https://docs.rs/search/{searchTerms}");
+        assert_eq!(engine.suggest_template, None);
+    }
+
+    #[test]
+    fn templates() {
+        let base = Url::parse(DESCRIPTOR_URL).unwrap();
+        let cases = [
+            ("https://a.com/?q={searchTerms}", Ok("https://a.com/?q={searchTerms}")),
+            ("/s?q={searchTerms}&ie={inputEncoding}", Ok("https://docs.rs/s?q={searchTerms}&ie=UTF-8")),
+            ("https://a.com/?q={searchTerms}&l={language}&p={startPage}", Ok("https://a.com/?q={searchTerms}&l=*&p=1")),
+            ("https://a.com/?q={searchTerms}&c={count?}&x={ext:foo?}", Ok("https://a.com/?q={searchTerms}&c=&x=")),
+            ("https://a.com/?c={count}&q={searchTerms}", Err(OpenSearchError::UnsupportedParameter("count".into()))),
+            ("https://a.com/", Err(OpenSearchError::NoHtmlTemplate)),
+            ("javascript:{searchTerms}", Err(OpenSearchError::InvalidUrl("javascript:{searchTerms}".into()))),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(convert_template(template, &base), expected.map(str::to_string), "{:?}", template);
+        }
+    }
+
+    #[test]
+    fn rejects_unusable_descriptors() {
+        let unnamed = descriptor(r#"<Url type="text/html" template="/s?q={searchTerms}"/>"#)
+            .replace("<ShortName>Docs.rs &amp; crates</ShortName>", "");
+        let rss_only = descriptor(r#"<Url type="application/rss+xml" template="/rss?q={searchTerms}"/>"#);
+        let post_only = descriptor(r#"<Url type="text/html" method="post" template="/s?q={searchTerms}"/>"#);
+        let cases = [
+            ("<html></html>".to_string(), OpenSearchError::NotOpenSearch),
+            (descriptor(&" ".repeat(MAX_DESCRIPTOR_BYTES)), OpenSearchError::TooLarge),
+            (unnamed, OpenSearchError::MissingName),
+            (rss_only, OpenSearchError::NoHtmlTemplate),
+            (post_only, OpenSearchError::NoHtmlTemplate),
+        ];
+        for (xml, expected) in cases {
+            assert_eq!(parsed(&xml).unwrap_err(), expected, "{}", xml);
+        }
+    }
+
+    #[test]
+    fn broken_suggestions_keep_the_engine() {
+        let xml = descriptor(
+            r#"<Url type="text/html" template="/s?q={searchTerms}"/>
+  <Url type="application/x-suggestions+json" template="/suggest?q={searchTerms}&amp;n={count}"/>"#,
+        );
+        let engine = parsed(&xml).unwrap();
+        assert_eq!(engine.url_template, "https://docs.rs/s?q={searchTerms}");
+        assert_eq!(engine.suggest_template, None);
+    }
+
+    #[test]
+    fn keywords() {
+        let registry = SearchEngineRegistry::default();
+        let cases = [
+            ("docs.rs", "docs"),
+            ("www.github.com", "github"),
+            ("en.wikipedia.org", "en"),
+            ("my-site.org", "mysite"),
+            ("", "site"),
+            // `g` and `w` belong to Google and Wikipedia
+            ("g.co", "g2"),
+            ("www.w.org", "w2"),
+        ];
+        for (host, expected) in cases {
+            assert_eq!(suggest_keyword(host, &registry), expected, "{:?}", host);
+        }
+    }
+}
@@ -0,0 +1,426 @@
+// process_memory.rs - Memory and CPU use of the browser's whole process tree, WebKit children included
+use serde::{Serialize, Deserialize};
+
+/// Clock ticks per second in /proc/<pid>/stat. USER_HZ is fixed at 100 on
+/// every mainstream architecture, whatever the kernel's internal tick rate.
+#[cfg(target_os = "linux")]
+const TICKS_PER_SEC: u64 = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessKind {
+    /// This process: the window, shell and BrowserCore.
+    Ui,
+    /// WebKitWebProcess, which renders pages.
+    Web,
+    /// WebKitNetworkProcess.
+    Network,
+    /// Anything else we spawned, e.g. the GPU process or sandbox helpers.
+    Other,
+}
+
+/// One process's memory, or a sum of several, in kB.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Resident pages, shared libraries counted in full by every process.
+    pub rss_kb: u64,
+    /// Resident pages with shared ones split between the processes using them.
+    /// `None` where smaps_rollup is unreadable.
+    pub pss_kb: Option<u64>,
+    /// Pages no other process maps, i.e. what exiting would free.
+    pub uss_kb: Option<u64>,
+    pub swap_kb: u64,
+    pub anon_kb: u64,
+    /// Page cache, mapped files and shared memory.
+    pub file_kb: u64,
+}
+
+impl MemoryUsage {
+    /// PSS when available, since RSS counts shared libraries once per process.
+    pub fn charged_kb(&self) -> u64 {
+        self.pss_kb.unwrap_or(self.rss_kb)
+    }
+
+    /// PSS and USS stay `None` unless every part had them, so a partial sum
+    /// never passes for a complete one.
+    fn sum<'a>(parts: impl Iterator<Item = &'a MemoryUsage>) -> Self {
+        let mut total = MemoryUsage { pss_kb: Some(0), uss_kb: Some(0), ..Default::default() };
+        for part in parts {
+            total.rss_kb += part.rss_kb;
+            total.pss_kb = total.pss_kb.zip(part.pss_kb).map(|(a, b)| a + b);
+            total.uss_kb = total.uss_kb.zip(part.uss_kb).map(|(a, b)| a + b);
+            total.swap_kb += part.swap_kb;
+            total.anon_kb += part.anon_kb;
+            total.file_kb += part.file_kb;
+        }
+        total
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProcessMemory {
+    pub pid: u32,
+    pub kind: ProcessKind,
+    pub name: String,
+    #[serde(flatten)]
+    pub usage: MemoryUsage,
+    /// User plus system CPU time since the process started.
+    pub cpu_time_ms: Option<u64>,
+    /// Seconds since the process started.
+    pub age_secs: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MemoryBreakdown {
+    pub ui_mb: usize,
+    pub web_mb: usize,
+    pub network_mb: usize,
+    pub other_mb: usize,
+    /// All processes together.
+    pub totals: MemoryUsage,
+    pub processes: Vec<ProcessMemory>,
+}
+
+impl MemoryBreakdown {
+    fn from_processes(processes: Vec<ProcessMemory>) -> Self {
+        let total = |kind: ProcessKind| -> usize {
+            let kb: u64 = processes.iter().filter(|p| p.kind == kind).map(|p| p.usage.charged_kb()).sum();
+            (kb / 1024) as usize
+        };
+        Self {
+            ui_mb: total(ProcessKind::Ui),
+            web_mb: total(ProcessKind::Web),
+            network_mb: total(ProcessKind::Network),
+            other_mb: total(ProcessKind::Other),
+            totals: MemoryUsage::sum(processes.iter().map(|p| &p.usage)),
+            processes,
+        }
+    }
+
+    pub fn total_mb(&self) -> usize {
+        let kb: u64 = self.processes.iter().map(|p| p.usage.charged_kb()).sum();
+        (kb / 1024) as usize
+    }
+}
+
+/// Samples this process and all of its descendants. `None` off Linux or if
+/// /proc can't be read, in which case callers fall back to their own RSS.
+#[cfg(target_os = "linux")]
+pub fn sample() -> Option<MemoryBreakdown> {
+    let own_pid = std::process::id();
+    let uptime = uptime_secs();
+    let mut processes = vec![read_process(own_pid, ProcessKind::Ui, uptime)?];
+    for pid in descendants(own_pid).ok()? {
+        let kind = classify(&process_name(pid));
+        // Children can exit between listing and reading; skip them.
+        if let Some(process) = read_process(pid, kind, uptime) {
+            processes.push(process);
+        }
+    }
+    Some(MemoryBreakdown::from_processes(processes))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn sample() -> Option<MemoryBreakdown> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn classify(name: &str) -> ProcessKind {
+    // comm is truncated to 15 bytes, so match on prefixes.
+    if name.starts_with("WebKitWebProc") {
+        ProcessKind::Web
+    } else if name.starts_with("WebKitNetwork") {
+        ProcessKind::Network
+    } else {
+        ProcessKind::Other
+    }
+}
+
+/// Every process below `root`. WebKit may start its children through a
+/// sandbox launcher, so grandchildren count too. Each process's threads list
+/// their children in /proc/<pid>/task/<tid>/children, so only the tree
+/// itself is read; kernels built without that file get a scan of /proc.
+#[cfg(target_os = "linux")]
+fn descendants(root: u32) -> std::io::Result<Vec<u32>> {
+    if !std::path::Path::new(&format!("/proc/{}/task/{}/children", root, root)).exists() {
+        return descendants_by_scan(root);
+    }
+    let mut found = Vec::new();
+    let mut pending = vec![root];
+    while let Some(pid) = pending.pop() {
+        for child in children(pid) {
+            found.push(child);
+            pending.push(child);
+        }
+    }
+    found.sort_unstable();
+    Ok(found)
+}
+
+/// Children of all of `pid`'s threads. Processes and threads can exit
+/// while this runs; those simply have none.
+#[cfg(target_os = "linux")]
+fn children(pid: u32) -> Vec<u32> {
+    let Ok(tasks) = std::fs::read_dir(format!("/proc/{}/task", pid)) else {
+        return Vec::new();
+    };
+    tasks
+        .filter_map(|task| std::fs::read_to_string(task.ok()?.path().join("children")).ok())
+        .flat_map(|list| list.split_whitespace().filter_map(|child| child.parse().ok()).collect::<Vec<_>>())
+        .collect()
+}
+
+/// Finds descendants by reading every process's parent pid.
+#[cfg(target_os = "linux")]
+fn descendants_by_scan(root: u32) -> std::io::Result<Vec<u32>> {
+    let mut children: std::collections::HashMap<u32, Vec<u32>> = std::collections::HashMap::new();
+    for entry in std::fs::read_dir("/proc")? {
+        let Some(pid) = entry.ok().and_then(|e| e.file_name().to_str()?.parse::<u32>().ok()) else {
+            continue;
+        };
+        if let Some(ppid) = parent_pid(pid) {
+            children.entry(ppid).or_default().push(pid);
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut pending = vec![root];
+    while let Some(pid) = pending.pop() {
+        for &child in children.get(&pid).map(Vec::as_slice).unwrap_or_default() {
+            found.push(child);
+            pending.push(child);
+        }
+    }
+    found.sort_unstable();
+    Ok(found)
+}
+
+/// The fields of /proc/<pid>/stat after the process name, so index 0 is
+/// field 3 (state) in proc(5) numbering.
+#[cfg(target_os = "linux")]
+fn stat_fields(pid: u32) -> Option<Vec<u64>> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The name in parentheses may itself contain spaces or parentheses.
+    let after_name = &stat[stat.rfind(')')? + 1..];
+    // The state letter becomes 0; it's only kept so positions line up.
+    Some(after_name.split_whitespace().map(|field| field.parse().unwrap_or(0)).collect())
+}
+
+#[cfg(target_os = "linux")]
+fn parent_pid(pid: u32) -> Option<u32> {
+    stat_fields(pid)?.get(1).map(|&ppid| ppid as u32)
+}
+
+/// CPU time in milliseconds and age in seconds.
+#[cfg(target_os = "linux")]
+fn process_times(pid: u32, uptime: Option<f64>) -> (Option<u64>, Option<u64>) {
+    let Some(fields) = stat_fields(pid) else {
+        return (None, None);
+    };
+    // utime, stime and starttime are fields 14, 15 and 22.
+    let cpu_time_ms = fields.get(11).zip(fields.get(12)).map(|(user, system)| (user + system) * 1000 / TICKS_PER_SEC);
+    let age_secs = fields.get(19).zip(uptime).map(|(&start, uptime)| {
+        (uptime - start as f64 / TICKS_PER_SEC as f64).max(0.0) as u64
+    });
+    (cpu_time_ms, age_secs)
+}
+
+#[cfg(target_os = "linux")]
+fn uptime_secs() -> Option<f64> {
+    std::fs::read_to_string("/proc/uptime").ok()?.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn process_name(pid: u32) -> String {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .map(|name| name.trim_end().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+fn read_process(pid: u32, kind: ProcessKind, uptime: Option<f64>) -> Option<ProcessMemory> {
+    let usage = match std::fs::read_to_string(format!("/proc/{}/smaps_rollup", pid)) {
+        Ok(rollup) => usage_from_rollup(&rollup),
+        // Not readable for processes we can't ptrace, or on kernels before 4.14.
+        Err(_) => usage_from_status(&std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?),
+    };
+    let (cpu_time_ms, age_secs) = process_times(pid, uptime);
+    Some(ProcessMemory {
+        pid,
+        kind,
+        name: process_name(pid),
+        usage,
+        cpu_time_ms,
+        age_secs,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn usage_from_rollup(rollup: &str) -> MemoryUsage {
+    let field = |name| kb_field(rollup, name).unwrap_or(0);
+    let rss_kb = field("Rss:");
+    let anon_kb = field("Anonymous:");
+    MemoryUsage {
+        rss_kb,
+        pss_kb: kb_field(rollup, "Pss:"),
+        uss_kb: Some(field("Private_Clean:") + field("Private_Dirty:")),
+        swap_kb: field("Swap:"),
+        anon_kb,
+        file_kb: rss_kb.saturating_sub(anon_kb),
+    }
+}
+
+/// The coarser per-process figures, without any split of shared pages.
+#[cfg(target_os = "linux")]
+fn usage_from_status(status: &str) -> MemoryUsage {
+    let field = |name| kb_field(status, name).unwrap_or(0);
+    MemoryUsage {
+        rss_kb: field("VmRSS:"),
+        pss_kb: None,
+        uss_kb: None,
+        swap_kb: field("VmSwap:"),
+        anon_kb: field("RssAnon:"),
+        file_kb: field("RssFile:") + field("RssShmem:"),
+    }
+}
+
+/// Reads a `Name:   1234 kB` line.
+#[cfg(target_os = "linux")]
+fn kb_field(content: &str, name: &str) -> Option<u64> {
+    content
+        .lines()
+        .find(|line| line.starts_with(name))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// /proc/<pid>/smaps_rollup from a WebKitWebProcess on Linux 6.1.
+    #[cfg(target_os = "linux")]
+    const ROLLUP: &str = "\
+55d8c9a5e000-7ffd2b1f2000 ---p 00000000 00:00 0                          [rollup]
+Rss:              123456 kB
+Pss:               65432 kB
+Pss_Dirty:         40000 kB
+Pss_Anon:          38000 kB
+Pss_File:          25000 kB
+Pss_Shmem:          2432 kB
+Shared_Clean:      50000 kB
+Shared_Dirty:       8000 kB
+Private_Clean:     20000 kB
+Private_Dirty:     45456 kB
+Referenced:       120000 kB
+Anonymous:         60000 kB
+LazyFree:              0 kB
+AnonHugePages:      2048 kB
+ShmemPmdMapped:        0 kB
+FilePmdMapped:         0 kB
+Shared_Hugetlb:        0 kB
+Private_Hugetlb:       0 kB
+Swap:               1024 kB
+SwapPss:             512 kB
+Locked:                0 kB
+";
+
+    /// The memory lines of /proc/<pid>/status for the same process.
+    #[cfg(target_os = "linux")]
+    const STATUS: &str = "\
+Name:\tWebKitWebProces
+VmPeak:\t 3000000 kB
+VmSize:\t 2900000 kB
+VmHWM:\t  250000 kB
+VmRSS:\t  200000 kB
+RssAnon:\t  150000 kB
+RssFile:\t   45000 kB
+RssShmem:\t    5000 kB
+VmSwap:\t    2048 kB
+";
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_rollup() {
+        assert_eq!(usage_from_rollup(ROLLUP), MemoryUsage {
+            rss_kb: 123_456,
+            pss_kb: Some(65_432),
+            // Private_Clean + Private_Dirty
+            uss_kb: Some(65_456),
+            swap_kb: 1024,
+            anon_kb: 60_000,
+            // Rss - Anonymous
+            file_kb: 63_456,
+        });
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn field_names_match_whole() {
+        // Without Pss: and Swap:, their Pss_Anon: and SwapPss: cousins must not stand in.
+        let rollup: String = ROLLUP
+            .lines()
+            .filter(|line| !line.starts_with("Pss:") && !line.starts_with("Swap:"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let usage = usage_from_rollup(&rollup);
+        assert_eq!((usage.pss_kb, usage.swap_kb), (None, 0));
+
+        let cases = [
+            ("Pss:", Some(65_432)),
+            ("Pss_Anon:", Some(38_000)),
+            ("Swap:", Some(1024)),
+            ("SwapPss:", Some(512)),
+            ("Private_Hugetlb:", Some(0)),
+            ("Uss:", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(kb_field(ROLLUP, name), expected, "{}", name);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_status() {
+        assert_eq!(usage_from_status(STATUS), MemoryUsage {
+            rss_kb: 200_000,
+            pss_kb: None,
+            uss_kb: None,
+            swap_kb: 2048,
+            anon_kb: 150_000,
+            // RssFile + RssShmem
+            file_kb: 50_000,
+        });
+    }
+
+    fn usage(rss_kb: u64, pss_kb: Option<u64>, uss_kb: Option<u64>) -> MemoryUsage {
+        MemoryUsage { rss_kb, pss_kb, uss_kb, swap_kb: 1, anon_kb: 2, file_kb: 3 }
+    }
+
+    #[test]
+    fn sums() {
+        let cases = [
+            (vec![], usage(0, Some(0), Some(0))),
+            (vec![usage(10, Some(5), Some(4))], usage(10, Some(5), Some(4))),
+            (vec![usage(10, Some(5), Some(4)), usage(20, Some(8), Some(6))], usage(30, Some(13), Some(10))),
+            // One process read from status makes the PSS and USS totals unknown
+            (vec![usage(10, Some(5), Some(4)), usage(20, None, None)], usage(30, None, None)),
+            (vec![usage(10, None, Some(4)), usage(20, Some(8), Some(6))], usage(30, None, Some(10))),
+        ];
+        for (parts, expected) in cases {
+            let mut total = MemoryUsage::sum(parts.iter());
+            // The helper's fixed fields add up per part
+            let n = parts.len() as u64;
+            assert_eq!((total.swap_kb, total.anon_kb, total.file_kb), (n, 2 * n, 3 * n));
+            (total.swap_kb, total.anon_kb, total.file_kb) = (1, 2, 3);
+            assert_eq!(total, expected, "{:?}", parts);
+        }
+    }
+
+    #[test]
+    fn charges_pss_when_known() {
+        assert_eq!(usage(10, Some(5), None).charged_kb(), 5);
+        assert_eq!(usage(10, None, None).charged_kb(), 10);
+    }
+}
@@ -0,0 +1,297 @@
+// redirects.rs - User-defined URL redirect rules stored in settings
+use std::fmt;
+use std::sync::OnceLock;
+use serde::{Serialize, Deserialize};
+use regex::Regex;
+use url::Url;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    /// `*` matches any run of characters and each becomes `$1`, `$2`...
+    /// Everything else, `?` included, matches literally.
+    #[default]
+    Glob,
+    /// Rust regex syntax; the target may use `$1` or `${name}`.
+    Regex,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RedirectRule {
+    /// Assigned on save; 0 for a rule that hasn't been saved yet.
+    #[serde(default)]
+    pub id: u64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub kind: PatternKind,
+    pub pattern: String,
+    /// The new URL, with capture references from the pattern.
+    pub target: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// Built on first use, so navigation doesn't recompile the pattern.
+    /// Saving replaces the whole rule, which drops it.
+    #[serde(skip)]
+    compiled: OnceLock<Regex>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl RedirectRule {
+    fn new(id: u64, name: &str, pattern: &str, target: &str) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            kind: PatternKind::Glob,
+            pattern: pattern.to_string(),
+            target: target.to_string(),
+            enabled: false,
+            compiled: OnceLock::new(),
+        }
+    }
+
+    fn compile(&self) -> Result<&Regex, RedirectError> {
+        if let Some(pattern) = self.compiled.get() {
+            return Ok(pattern);
+        }
+        let source = match self.kind {
+            PatternKind::Glob => glob_to_regex(&self.pattern),
+            PatternKind::Regex => self.pattern.clone(),
+        };
+        let pattern = Regex::new(&source).map_err(|e| RedirectError::InvalidPattern(e.to_string()))?;
+        Ok(self.compiled.get_or_init(|| pattern))
+    }
+
+    /// Where this rule sends `url`, or `None` if it doesn't match.
+    pub fn rewrite(&self, url: &str) -> Result<Option<Url>, RedirectError> {
+        let pattern = self.compile()?;
+        let Some(captures) = pattern.captures(url) else {
+            return Ok(None);
+        };
+        let mut target = String::new();
+        captures.expand(&self.target, &mut target);
+        match Url::parse(&target) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(Some(parsed)),
+            _ => Err(RedirectError::InvalidTarget(target)),
+        }
+    }
+}
+
+/// Anchored and case-insensitive, since hosts are. Each `*` captures; `?`
+/// is left literal because it starts every query string.
+fn glob_to_regex(glob: &str) -> String {
+    let literals: Vec<String> = glob.split('*').map(regex::escape).collect();
+    format!("(?i)^{}$", literals.join("(.*)"))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RedirectError {
+    InvalidPattern(String),
+    /// The expanded target isn't an http(s) URL.
+    InvalidTarget(String),
+    EmptyPattern,
+    NotFound(u64),
+}
+
+impl fmt::Display for RedirectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedirectError::InvalidPattern(e) => write!(f, "Invalid pattern: {}", e),
+            RedirectError::InvalidTarget(t) => write!(f, "Redirect target is not an http(s) URL: {}", t),
+            RedirectError::EmptyPattern => write!(f, "Pattern and target are required"),
+            RedirectError::NotFound(id) => write!(f, "No redirect rule with id {}", id),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RedirectRules {
+    rules: Vec<RedirectRule>,
+    next_id: u64,
+}
+
+impl Default for RedirectRules {
+    /// Common front-end swaps, off until the user enables them.
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                RedirectRule {
+                    kind: PatternKind::Regex,
+                    ..RedirectRule::new(
+                        1,
+                        "Old Reddit",
+                        r"(?i)^https://(?:www\.|m\.)?reddit\.com/(.*)$",
+                        "https://old.reddit.com/$1",
+                    )
+                },
+                RedirectRule::new(2, "Medium via Scribe", "https://medium.com/*", "https://scribe.rip/$1"),
+            ],
+            next_id: 3,
+        }
+    }
+}
+
+impl RedirectRules {
+    pub fn rules(&self) -> &[RedirectRule] {
+        &self.rules
+    }
+
+    /// The first enabled rule that rewrites `url`. A rule that fails to
+    /// compile or expand is skipped rather than blocking navigation.
+    pub fn apply(&self, url: &Url) -> Option<(Url, &RedirectRule)> {
+        self.rules.iter().filter(|r| r.enabled).find_map(|rule| match rule.rewrite(url.as_str()) {
+            Ok(Some(target)) if target != *url => Some((target, rule)),
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("Skipping redirect rule {:?}: {}", rule.name, e);
+                None
+            }
+        })
+    }
+
+    /// Adds the rule if its id is 0, otherwise replaces the rule with that id.
+    pub fn save(&mut self, mut rule: RedirectRule) -> Result<u64, RedirectError> {
+        rule.pattern = rule.pattern.trim().to_string();
+        rule.target = rule.target.trim().to_string();
+        rule.name = rule.name.trim().to_string();
+        if rule.pattern.is_empty() || rule.target.is_empty() {
+            return Err(RedirectError::EmptyPattern);
+        }
+        rule.compile()?;
+        if rule.name.is_empty() {
+            rule.name = rule.pattern.clone();
+        }
+
+        if rule.id == 0 {
+            self.next_id = self.next_id.max(1);
+            rule.id = self.next_id;
+            self.next_id += 1;
+            self.rules.push(rule.clone());
+        } else {
+            let existing = self.find_mut(rule.id)?;
+            *existing = rule.clone();
+        }
+        Ok(rule.id)
+    }
+
+    pub fn remove(&mut self, id: u64) -> Result<(), RedirectError> {
+        let before = self.rules.len();
+        self.rules.retain(|r| r.id != id);
+        if self.rules.len() == before {
+            return Err(RedirectError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    pub fn set_enabled(&mut self, id: u64, enabled: bool) -> Result<(), RedirectError> {
+        self.find_mut(id)?.enabled = enabled;
+        Ok(())
+    }
+
+    fn find_mut(&mut self, id: u64) -> Result<&mut RedirectRule, RedirectError> {
+        self.rules.iter_mut().find(|r| r.id == id).ok_or(RedirectError::NotFound(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str, target: &str) -> RedirectRule {
+        RedirectRule::new(0, "test", pattern, target)
+    }
+
+    fn rewritten(rule: &RedirectRule, url: &str) -> Option<String> {
+        rule.rewrite(url).unwrap().map(String::from)
+    }
+
+    #[test]
+    fn glob_patterns() {
+        let cases = [
+            ("https://example.com/*", r"(?i)^https://example\.com/(.*)$"),
+            ("*://*.example.com/*", r"(?i)^(.*)://(.*)\.example\.com/(.*)$"),
+            ("https://example.com/search?q=*", r"(?i)^https://example\.com/search\?q=(.*)$"),
+            ("https://example.com/a+b(c)", r"(?i)^https://example\.com/a\+b\(c\)$"),
+            ("", "(?i)^$"),
+        ];
+        for (pattern, expected) in cases {
+            assert_eq!(glob_to_regex(pattern), expected, "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn glob_rewrites() {
+        let medium = glob("https://medium.com/*", "https://scribe.rip/$1");
+        let subdomain = glob("https://*.example.com/*", "https://example.org/$1/$2");
+        // `?` is literal, so it only matches a query separator
+        let query = glob("https://a.com/s?q=*", "https://b.com/?q=$1");
+        let cases = [
+            (&medium, "https://medium.com/@a/post", Some("https://scribe.rip/@a/post")),
+            (&medium, "HTTPS://MEDIUM.COM/x", Some("https://scribe.rip/x")),
+            (&medium, "https://medium.com.evil/x", None),
+            (&subdomain, "https://docs.example.com/a", Some("https://example.org/docs/a")),
+            (&query, "https://a.com/s?q=rust", Some("https://b.com/?q=rust")),
+            (&query, "https://a.com/sxq=rust", None),
+        ];
+        for (rule, url, expected) in cases {
+            assert_eq!(rewritten(rule, url).as_deref(), expected, "{} on {}", rule.pattern, url);
+        }
+    }
+
+    #[test]
+    fn regex_rewrites() {
+        let rule = RedirectRule {
+            kind: PatternKind::Regex,
+            ..glob(r"^https://(?P<host>[a-z]+)\.wikipedia\.org/wiki/(.*)$", "https://${host}.m.wikipedia.org/wiki/$2")
+        };
+        assert_eq!(
+            rewritten(&rule, "https://en.wikipedia.org/wiki/Rust").as_deref(),
+            Some("https://en.m.wikipedia.org/wiki/Rust")
+        );
+        assert_eq!(rewritten(&rule, "https://example.com/"), None);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let unclosed = RedirectRule { kind: PatternKind::Regex, ..glob("https://(", "https://a.com/") };
+        assert!(matches!(unclosed.rewrite("https://a.com/"), Err(RedirectError::InvalidPattern(_))));
+        let not_http = glob("https://a.com/*", "javascript:$1");
+        assert!(matches!(not_http.rewrite("https://a.com/x"), Err(RedirectError::InvalidTarget(_))));
+    }
+
+    #[test]
+    fn default_old_reddit() {
+        let mut rules = RedirectRules::default();
+        rules.set_enabled(1, true).unwrap();
+        let cases = [
+            ("https://www.reddit.com/r/rust", Some("https://old.reddit.com/r/rust")),
+            ("https://reddit.com/r/rust", Some("https://old.reddit.com/r/rust")),
+            ("https://m.reddit.com/r/rust", Some("https://old.reddit.com/r/rust")),
+            ("https://old.reddit.com/r/rust", None),
+            ("https://notreddit.com/r/rust", None),
+            ("https://example.com/?next=https://www.reddit.com/", None),
+        ];
+        for (url, expected) in cases {
+            let url = Url::parse(url).unwrap();
+            let target = rules.apply(&url).map(|(target, _)| target.to_string());
+            assert_eq!(target.as_deref(), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn save_assigns_ids_and_validates() {
+        let mut rules = RedirectRules::default();
+        let id = rules.save(glob(" https://a.com/* ", "https://b.com/$1")).unwrap();
+        assert_eq!(id, 3);
+        assert_eq!(rules.rules()[2].pattern, "https://a.com/*");
+        assert_eq!(rules.save(glob("", "https://b.com/")), Err(RedirectError::EmptyPattern));
+        let missing = RedirectRule { id: 42, ..glob("https://a.com/", "https://b.com/") };
+        assert_eq!(rules.save(missing), Err(RedirectError::NotFound(42)));
+        assert_eq!(rules.remove(id), Ok(()));
+        assert_eq!(rules.remove(id), Err(RedirectError::NotFound(id)));
+    }
+}
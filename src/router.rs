@@ -0,0 +1,267 @@
+// router.rs - Routes minimalist:// requests to internal page and API handlers
+use std::borrow::Cow;
+use std::collections::HashMap;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wry::http::{header, HeaderValue, Method, Request, Response, StatusCode};
+use crate::assets::browser_pages::{BrowserPage, BROWSER_PAGES};
+use crate::api;
+use crate::browser_core::BrowserCore;
+use crate::templates::PageContext;
+
+/// Pages rendered with live data through `templates` rather than served as-is.
+const TEMPLATED_PAGES: &[&str] = &["newtab", "memory", "settings"];
+
+/// A minimalist:// request with its query string already decoded.
+pub struct RouteRequest {
+    pub method: Method,
+    /// Without a trailing slash; `/` for the bare scheme.
+    pub path: String,
+    pub query: HashMap<String, String>,
+    /// The `Origin` header, if the request carried one.
+    pub origin: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl RouteRequest {
+    pub fn from_http(request: Request<Vec<u8>>) -> Self {
+        let uri = request.uri();
+        let path = match uri.path().trim_end_matches('/') {
+            "" => "/".to_string(),
+            path => path.to_string(),
+        };
+        let query = uri
+            .query()
+            .map(|q| url::form_urlencoded::parse(q.as_bytes()).into_owned().collect())
+            .unwrap_or_default();
+        let origin = request
+            .headers()
+            .get(header::ORIGIN)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Self {
+            method: request.method().clone(),
+            path,
+            query,
+            origin,
+            body: request.into_body(),
+        }
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query.get(name).map(String::as_str)
+    }
+
+    /// Decodes the JSON body. Mutations are never read from the query
+    /// string, so a plain link can't trigger one.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, String> {
+        if self.body.is_empty() {
+            return Err("Expected a JSON request body".to_string());
+        }
+        serde_json::from_slice(&self.body).map_err(|e| e.to_string())
+    }
+
+    /// Whether the request came from one of the browser's own pages. Web
+    /// pages can send requests to minimalist:// too, but not with this origin.
+    pub fn is_internal(&self) -> bool {
+        self.origin.as_deref().is_some_and(|origin| {
+            origin.starts_with("minimalist:")
+                || origin == "http://minimalist.localhost"
+                || origin == "https://minimalist.localhost"
+        })
+    }
+}
+
+pub struct RouteResponse {
+    pub status: StatusCode,
+    pub content_type: Cow<'static, str>,
+    pub headers: Vec<(header::HeaderName, String)>,
+    pub body: Cow<'static, [u8]>,
+}
+
+impl RouteResponse {
+    pub fn page(page: &'static BrowserPage) -> Self {
+        Self {
+            status: StatusCode::OK,
+            content_type: Cow::Borrowed(page.content_type),
+            headers: Vec::new(),
+            body: Cow::Borrowed(page.content.as_bytes()),
+        }
+    }
+
+    /// The error page with a 404, so a mistyped minimalist:// address says so.
+    pub fn not_found(path: &str) -> Self {
+        log::warn!("No internal page at minimalist://{}", path.trim_start_matches('/'));
+        match BROWSER_PAGES.get("error") {
+            Some(page) => Self::page(page),
+            None => Self::text("Page not found"),
+        }
+        .with_status(StatusCode::NOT_FOUND)
+    }
+
+    pub fn method_not_allowed(allowed: &[Method]) -> Self {
+        let allowed = allowed.iter().map(Method::as_str).collect::<Vec<_>>().join(", ");
+        Self::text("Method not allowed")
+            .with_status(StatusCode::METHOD_NOT_ALLOWED)
+            .with_header(header::ALLOW, allowed)
+    }
+
+    pub fn html(body: String) -> Self {
+        Self {
+            status: StatusCode::OK,
+            content_type: Cow::Borrowed("text/html; charset=utf-8"),
+            headers: vec![(header::CACHE_CONTROL, "no-store".to_string())],
+            body: Cow::Owned(body.into_bytes()),
+        }
+    }
+
+    /// Offered as a download named `filename`.
+    pub fn csv(body: String, filename: &str) -> Self {
+        Self {
+            status: StatusCode::OK,
+            content_type: Cow::Borrowed("text/csv; charset=utf-8"),
+            headers: vec![(header::CACHE_CONTROL, "no-store".to_string())],
+            body: Cow::Owned(body.into_bytes()),
+        }
+        .with_header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename))
+    }
+
+    pub fn json<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Self {
+                status: StatusCode::OK,
+                content_type: Cow::Borrowed("application/json"),
+                headers: vec![(header::CACHE_CONTROL, "no-store".to_string())],
+                body: Cow::Owned(body),
+            },
+            Err(e) => {
+                log::warn!("Failed to serialize API response: {}", e);
+                Self::text("Serialization failed").with_status(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    pub fn text(body: &'static str) -> Self {
+        Self {
+            status: StatusCode::OK,
+            content_type: Cow::Borrowed("text/plain; charset=utf-8"),
+            headers: Vec::new(),
+            body: Cow::Borrowed(body.as_bytes()),
+        }
+    }
+
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_header(mut self, name: header::HeaderName, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    fn into_http(self) -> Response<Cow<'static, [u8]>> {
+        let mut builder = Response::builder()
+            .status(self.status)
+            .header(header::CONTENT_TYPE, self.content_type.as_ref());
+        for (name, value) in self.headers {
+            match HeaderValue::from_str(&value) {
+                Ok(value) => builder = builder.header(name, value),
+                Err(_) => log::warn!("Dropping invalid {} header on internal page", name),
+            }
+        }
+        builder.body(self.body).unwrap_or_else(|e| {
+            log::warn!("Failed to build internal page response: {}", e);
+            let mut response = Response::new(Cow::Borrowed(&b"Internal error"[..]));
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        })
+    }
+}
+
+/// Serves one minimalist:// path.
+pub trait RouteHandler: Send + Sync {
+    fn handle(&self, request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse;
+}
+
+/// A fixed page from `BROWSER_PAGES`.
+pub struct StaticPage(pub &'static BrowserPage);
+
+impl RouteHandler for StaticPage {
+    fn handle(&self, request: &RouteRequest, _core: &mut BrowserCore) -> RouteResponse {
+        match request.method {
+            Method::GET | Method::HEAD => RouteResponse::page(self.0),
+            _ => RouteResponse::method_not_allowed(&[Method::GET, Method::HEAD]),
+        }
+    }
+}
+
+/// Plain functions and closures work as handlers, which suits API endpoints.
+impl<F> RouteHandler for F
+where
+    F: Fn(&RouteRequest, &mut BrowserCore) -> RouteResponse + Send + Sync,
+{
+    fn handle(&self, request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse {
+        self(request, core)
+    }
+}
+
+/// A page from `BROWSER_PAGES` with `{{name}}` placeholders filled from the core.
+pub struct TemplatedPage(pub &'static BrowserPage);
+
+impl RouteHandler for TemplatedPage {
+    fn handle(&self, request: &RouteRequest, core: &mut BrowserCore) -> RouteResponse {
+        match request.method {
+            Method::GET | Method::HEAD => {
+                RouteResponse::html(PageContext::from_core(core).render(self.0.content))
+            }
+            _ => RouteResponse::method_not_allowed(&[Method::GET, Method::HEAD]),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Router {
+    routes: HashMap<String, Box<dyn RouteHandler>>,
+}
+
+impl Router {
+    /// Every page in `BROWSER_PAGES` at `/<name>`, plus `/` for the new tab
+    /// page and the JSON API under `/api/`.
+    pub fn with_builtin_pages() -> Self {
+        let mut router = Self::default();
+        for (name, page) in BROWSER_PAGES.iter() {
+            let path = format!("/{}", name);
+            if TEMPLATED_PAGES.contains(name) {
+                router.route(&path, TemplatedPage(page));
+            } else {
+                router.route(&path, StaticPage(page));
+            }
+        }
+        if let Some(newtab) = BROWSER_PAGES.get("newtab") {
+            router.route("/", TemplatedPage(newtab));
+        }
+        api::register(&mut router);
+        router
+    }
+
+    /// Registers `handler` for `path`, replacing any handler already there.
+    pub fn route(&mut self, path: &str, handler: impl RouteHandler + 'static) -> &mut Self {
+        self.routes.insert(path.to_string(), Box::new(handler));
+        self
+    }
+
+    pub fn handle(&self, request: Request<Vec<u8>>, core: &mut BrowserCore) -> Response<Cow<'static, [u8]>> {
+        let request = RouteRequest::from_http(request);
+        let read = matches!(request.method, Method::GET | Method::HEAD);
+        let response = match self.routes.get(&request.path) {
+            Some(_) if !read && !request.is_internal() => {
+                log::warn!("Refusing {} to minimalist:/{} from {:?}", request.method, request.path, request.origin);
+                RouteResponse::text("Forbidden").with_status(StatusCode::FORBIDDEN)
+            }
+            Some(handler) => handler.handle(&request, core),
+            None => RouteResponse::not_found(&request.path),
+        };
+        response.into_http()
+    }
+}
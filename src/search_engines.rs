@@ -0,0 +1,293 @@
+// search_engines.rs - Search engine registry stored in settings
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use lazy_static::lazy_static;
+
+/// Placeholder for the query in URL templates, as in OpenSearch descriptors.
+pub const SEARCH_TERMS: &str = "{searchTerms}";
+
+lazy_static! {
+    /// Used only if settings.json was edited down to no engines at all.
+    static ref FALLBACK_ENGINE: SearchEngine = SearchEngineRegistry::default().engines.remove(0);
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SearchEngine {
+    pub name: String,
+    /// Typed before a query (`w rust lifetimes`) to search this engine.
+    pub keyword: String,
+    pub url_template: String,
+    /// Returns OpenSearch suggestion JSON (`[query, [suggestions...]]`).
+    #[serde(default)]
+    pub suggest_template: Option<String>,
+    /// OpenSearch descriptor the engine was installed from, if any.
+    #[serde(default)]
+    pub descriptor_url: Option<String>,
+}
+
+impl SearchEngine {
+    fn new(name: &str, keyword: &str, url_template: &str, suggest_template: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            keyword: keyword.to_string(),
+            url_template: url_template.to_string(),
+            suggest_template: suggest_template.map(str::to_string),
+            descriptor_url: None,
+        }
+    }
+
+    pub fn search_url(&self, query: &str) -> String {
+        fill_template(&self.url_template, query)
+    }
+
+    pub fn suggest_url(&self, query: &str) -> Option<String> {
+        self.suggest_template.as_deref().map(|t| fill_template(t, query))
+    }
+}
+
+pub(crate) fn fill_template(template: &str, query: &str) -> String {
+    template.replace(SEARCH_TERMS, &urlencoding::encode(query))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SearchEngineError {
+    NotFound(String),
+    DuplicateKeyword(String),
+    InvalidKeyword,
+    MissingSearchTerms,
+    LastEngine,
+    /// No page has offered an OpenSearch engine from this descriptor.
+    NotDiscovered(String),
+}
+
+impl fmt::Display for SearchEngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchEngineError::NotFound(k) => write!(f, "No search engine with keyword '{}'", k),
+            SearchEngineError::DuplicateKeyword(k) => write!(f, "Keyword '{}' is already in use", k),
+            SearchEngineError::InvalidKeyword => write!(f, "Keywords must be a single word"),
+            SearchEngineError::MissingSearchTerms => {
+                write!(f, "Search URL must contain {} or %s", SEARCH_TERMS)
+            }
+            SearchEngineError::LastEngine => write!(f, "At least one search engine is required"),
+            SearchEngineError::NotDiscovered(url) => write!(f, "No page has offered a search engine from {}", url),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SearchEngineRegistry {
+    engines: Vec<SearchEngine>,
+    default_keyword: String,
+}
+
+impl Default for SearchEngineRegistry {
+    fn default() -> Self {
+        Self {
+            engines: vec![
+                SearchEngine::new(
+                    "Google",
+                    "g",
+                    "https://www.google.com/search?q={searchTerms}",
+                    Some("https://suggestqueries.google.com/complete/search?client=firefox&q={searchTerms}"),
+                ),
+                SearchEngine::new(
+                    "DuckDuckGo",
+                    "d",
+                    "https://duckduckgo.com/?q={searchTerms}",
+                    Some("https://duckduckgo.com/ac/?type=list&q={searchTerms}"),
+                ),
+                SearchEngine::new(
+                    "Wikipedia",
+                    "w",
+                    "https://en.wikipedia.org/wiki/Special:Search?search={searchTerms}",
+                    Some("https://en.wikipedia.org/w/api.php?action=opensearch&search={searchTerms}"),
+                ),
+                SearchEngine::new(
+                    "Bing",
+                    "b",
+                    "https://www.bing.com/search?q={searchTerms}",
+                    None,
+                ),
+            ],
+            default_keyword: "g".to_string(),
+        }
+    }
+}
+
+impl SearchEngineRegistry {
+    pub fn engines(&self) -> &[SearchEngine] {
+        &self.engines
+    }
+
+    /// The engine plain searches go to. Falls back to the first one if the
+    /// stored default was removed by hand-editing settings.json.
+    pub fn default_engine(&self) -> &SearchEngine {
+        self.find(&self.default_keyword)
+            .or_else(|| self.engines.first())
+            .unwrap_or(&FALLBACK_ENGINE)
+    }
+
+    pub fn find(&self, keyword: &str) -> Option<&SearchEngine> {
+        self.engines.iter().find(|e| e.keyword.eq_ignore_ascii_case(keyword))
+    }
+
+    pub fn has_descriptor(&self, descriptor_url: &str) -> bool {
+        self.engines.iter().any(|e| e.descriptor_url.as_deref() == Some(descriptor_url))
+    }
+
+    /// Splits `w rust lifetimes` into the Wikipedia engine and `rust lifetimes`.
+    pub fn split_keyword<'a>(&self, input: &'a str) -> Option<(&SearchEngine, &'a str)> {
+        let (keyword, query) = input.split_once(char::is_whitespace)?;
+        let query = query.trim();
+        if query.is_empty() {
+            return None;
+        }
+        self.find(keyword).map(|engine| (engine, query))
+    }
+
+    pub fn add(&mut self, mut engine: SearchEngine) -> Result<(), SearchEngineError> {
+        engine.name = engine.name.trim().to_string();
+        engine.keyword = engine.keyword.trim().to_string();
+        engine.url_template = normalize_template(&engine.url_template)?;
+        engine.suggest_template = match engine.suggest_template.as_deref().map(str::trim) {
+            Some("") | None => None,
+            Some(t) => Some(normalize_template(t)?),
+        };
+        if engine.keyword.is_empty() || engine.keyword.contains(char::is_whitespace) {
+            return Err(SearchEngineError::InvalidKeyword);
+        }
+        if self.find(&engine.keyword).is_some() {
+            return Err(SearchEngineError::DuplicateKeyword(engine.keyword));
+        }
+        if engine.name.is_empty() {
+            engine.name = engine.keyword.clone();
+        }
+        self.engines.push(engine);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, keyword: &str) -> Result<(), SearchEngineError> {
+        let index = self.position(keyword)?;
+        if self.engines.len() == 1 {
+            return Err(SearchEngineError::LastEngine);
+        }
+        let removed = self.engines.remove(index);
+        if removed.keyword == self.default_keyword {
+            self.default_keyword = self.engines[0].keyword.clone();
+        }
+        Ok(())
+    }
+
+    pub fn set_default(&mut self, keyword: &str) -> Result<(), SearchEngineError> {
+        let index = self.position(keyword)?;
+        self.default_keyword = self.engines[index].keyword.clone();
+        Ok(())
+    }
+
+    fn position(&self, keyword: &str) -> Result<usize, SearchEngineError> {
+        self.engines
+            .iter()
+            .position(|e| e.keyword.eq_ignore_ascii_case(keyword))
+            .ok_or_else(|| SearchEngineError::NotFound(keyword.to_string()))
+    }
+}
+
+/// Accepts Chrome-style `%s` as well as `{searchTerms}`.
+pub(crate) fn normalize_template(template: &str) -> Result<String, SearchEngineError> {
+    let template = template.trim().replace("%s", SEARCH_TERMS);
+    if template.contains(SEARCH_TERMS) {
+        Ok(template)
+    } else {
+        Err(SearchEngineError::MissingSearchTerms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(keyword: &str, url_template: &str) -> SearchEngine {
+        SearchEngine::new("", keyword, url_template, None)
+    }
+
+    #[test]
+    fn templates() {
+        let cases = [
+            ("https://a.com/?q=%s", Ok("https://a.com/?q={searchTerms}")),
+            (" https://a.com/?q={searchTerms} ", Ok("https://a.com/?q={searchTerms}")),
+            ("https://a.com/%s/%s", Ok("https://a.com/{searchTerms}/{searchTerms}")),
+            ("https://a.com/", Err(SearchEngineError::MissingSearchTerms)),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(normalize_template(template), expected.map(str::to_string), "{:?}", template);
+        }
+    }
+
+    #[test]
+    fn fills_encoded_queries() {
+        let cases = [
+            ("rust", "https://a.com/?q=rust"),
+            ("rust lifetimes", "https://a.com/?q=rust%20lifetimes"),
+            ("a&b=c#d", "https://a.com/?q=a%26b%3Dc%23d"),
+            ("ü", "https://a.com/?q=%C3%BC"),
+        ];
+        let engine = engine("a", "https://a.com/?q={searchTerms}");
+        for (query, expected) in cases {
+            assert_eq!(engine.search_url(query), expected, "{:?}", query);
+        }
+        assert_eq!(engine.suggest_url("rust"), None);
+    }
+
+    #[test]
+    fn splits_keywords() {
+        let registry = SearchEngineRegistry::default();
+        let cases = [
+            ("w rust lifetimes", Some(("w", "rust lifetimes"))),
+            ("W  rust ", Some(("w", "rust"))),
+            ("w", None),
+            ("w ", None),
+            ("x rust", None),
+            ("rust", None),
+        ];
+        for (input, expected) in cases {
+            let split = registry.split_keyword(input).map(|(e, q)| (e.keyword.as_str(), q));
+            assert_eq!(split, expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn add_validates() {
+        let mut registry = SearchEngineRegistry::default();
+        let cases = [
+            (engine("", "https://a.com/?q=%s"), Err(SearchEngineError::InvalidKeyword)),
+            (engine("two words", "https://a.com/?q=%s"), Err(SearchEngineError::InvalidKeyword)),
+            (engine("G", "https://a.com/?q=%s"), Err(SearchEngineError::DuplicateKeyword("G".to_string()))),
+            (engine("a", "https://a.com/"), Err(SearchEngineError::MissingSearchTerms)),
+            (engine(" a ", "https://a.com/?q=%s"), Ok(())),
+        ];
+        for (engine, expected) in cases {
+            let keyword = engine.keyword.clone();
+            assert_eq!(registry.add(engine), expected, "{:?}", keyword);
+        }
+        let added = registry.find("A").unwrap();
+        assert_eq!((added.name.as_str(), added.url_template.as_str()), ("a", "https://a.com/?q={searchTerms}"));
+    }
+
+    #[test]
+    fn default_follows_removal() {
+        let mut registry = SearchEngineRegistry::default();
+        assert_eq!(registry.default_engine().keyword, "g");
+        registry.set_default("D").unwrap();
+        assert_eq!(registry.default_engine().keyword, "d");
+        assert_eq!(registry.set_default("x"), Err(SearchEngineError::NotFound("x".to_string())));
+
+        registry.remove("d").unwrap();
+        assert_eq!(registry.default_engine().keyword, "g");
+        registry.remove("w").unwrap();
+        registry.remove("b").unwrap();
+        assert_eq!(registry.remove("g"), Err(SearchEngineError::LastEngine));
+        assert_eq!(registry.engines().len(), 1);
+    }
+}
@@ -0,0 +1,131 @@
+// session.rs - Saving and restoring BrowserCore state across runs
+use serde::{Serialize, Deserialize};
+use crate::browser_core::{BrowserCore, TabData};
+use crate::storage;
+
+const SESSION_FILE: &str = "session.json";
+/// Tabs from a run that crashed, kept until the user restores or dismisses
+/// them on minimalist://restore.
+const RECOVERED_FILE: &str = "recovered_session.json";
+const SESSION_VERSION: u32 = 1;
+/// Present while the browser runs; removed only on the `CloseRequested` path.
+const RUNNING_MARKER: &str = "running.lock";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionState {
+    pub version: u32,
+    pub tabs: Vec<TabData>,
+    pub active_tab_id: Option<usize>,
+    pub booster_mode: bool,
+    pub ultimate_boost: bool,
+    pub flash_enabled: bool,
+}
+
+impl SessionState {
+    pub fn new(
+        tabs: Vec<TabData>,
+        active_tab_id: Option<usize>,
+        booster_mode: bool,
+        ultimate_boost: bool,
+        flash_enabled: bool,
+    ) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            tabs,
+            active_tab_id,
+            booster_mode,
+            ultimate_boost,
+            flash_enabled,
+        }
+    }
+}
+
+pub fn save(core: &BrowserCore) {
+    if let Err(e) = storage::save_json(SESSION_FILE, &core.session_state()) {
+        log::warn!("Failed to save session: {}", e);
+    }
+}
+
+/// Returns the last saved session, if there is a usable one.
+pub fn load() -> Option<SessionState> {
+    match storage::load_json::<SessionState>(SESSION_FILE) {
+        Ok(Some(session)) if session.version == SESSION_VERSION && !session.tabs.is_empty() => {
+            Some(session)
+        }
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("Ignoring unreadable session file: {}", e);
+            None
+        }
+    }
+}
+
+/// Returns the tabs still waiting on minimalist://restore, if any.
+pub fn load_recovered() -> Option<SessionState> {
+    match storage::load_json::<SessionState>(RECOVERED_FILE) {
+        Ok(Some(session)) if session.version == SESSION_VERSION && !session.tabs.is_empty() => {
+            Some(session)
+        }
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("Ignoring unreadable recovered session file: {}", e);
+            None
+        }
+    }
+}
+
+/// Writes the tabs still waiting to be restored, or removes the file once
+/// none are left.
+pub fn save_recovered(session: Option<&SessionState>) {
+    match session.filter(|s| !s.tabs.is_empty()) {
+        Some(session) => {
+            if let Err(e) = storage::save_json(RECOVERED_FILE, session) {
+                log::warn!("Failed to save recovered session: {}", e);
+            }
+        }
+        None => {
+            let path = storage::data_path(RECOVERED_FILE);
+            if path.exists() {
+                if let Err(e) = std::fs::remove_file(path) {
+                    log::warn!("Failed to remove recovered session: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Adds the tabs of a run that just crashed to those still waiting from an
+/// earlier crash. Restore pages are dropped, and the crashed run's tabs are
+/// renumbered so the ids stay unique for `restore_recovered_tabs`.
+pub fn merge_recovered(waiting: Option<SessionState>, crashed: Option<SessionState>) -> Option<SessionState> {
+    let Some(mut crashed) = crashed else {
+        return waiting;
+    };
+    crashed.tabs.retain(|t| !t.url.starts_with("minimalist://restore"));
+    let Some(mut waiting) = waiting else {
+        return Some(crashed).filter(|s| !s.tabs.is_empty());
+    };
+    let first_id = waiting.tabs.iter().map(|t| t.id + 1).max().unwrap_or(0);
+    for (id, mut tab) in (first_id..).zip(crashed.tabs) {
+        tab.id = id;
+        waiting.tabs.push(tab);
+    }
+    Some(waiting)
+}
+
+/// Marks this run as in progress. Returns `true` if the previous run never
+/// reached a clean shutdown (its marker is still there).
+pub fn begin_run() -> bool {
+    let marker = storage::data_path(RUNNING_MARKER);
+    let unclean = marker.exists();
+    if let Err(e) = std::fs::write(&marker, std::process::id().to_string()) {
+        log::warn!("Failed to write run marker: {}", e);
+    }
+    unclean
+}
+
+pub fn end_run() {
+    if let Err(e) = std::fs::remove_file(storage::data_path(RUNNING_MARKER)) {
+        log::warn!("Failed to remove run marker: {}", e);
+    }
+}
@@ -0,0 +1,63 @@
+// settings.rs - User preferences persisted in browser_data/settings.json
+use serde::{Serialize, Deserialize};
+use crate::redirects::RedirectRules;
+use crate::search_engines::SearchEngineRegistry;
+use crate::storage;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// Reopen the previous session's tabs on startup instead of a single new tab.
+    pub restore_session: bool,
+    /// Keep the text of visited pages so minimalist://search can find them.
+    pub index_page_content: bool,
+    /// Strip tracking parameters and unwrap redirect links before navigating.
+    pub clean_urls: bool,
+    /// Load http:// addresses over https, showing an interstitial if that fails.
+    pub https_only: bool,
+    /// Minutes a background tab may sit unused before it is discarded; 0 never.
+    pub discard_after_mins: u64,
+    /// Let the memory watchdog switch on Ultimate Boost, which clears site
+    /// storage, between Booster and discarding tabs. On unless turned off.
+    pub auto_ultimate_boost: bool,
+    /// Hosts the user chose to always load over plain http.
+    pub http_exceptions: Vec<String>,
+    pub search_engines: SearchEngineRegistry,
+    pub redirects: RedirectRules,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            restore_session: true,
+            index_page_content: false,
+            clean_urls: true,
+            https_only: false,
+            discard_after_mins: 30,
+            auto_ultimate_boost: true,
+            http_exceptions: Vec::new(),
+            search_engines: SearchEngineRegistry::default(),
+            redirects: RedirectRules::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        match storage::load_json(SETTINGS_FILE) {
+            Ok(settings) => settings.unwrap_or_default(),
+            Err(e) => {
+                log::warn!("Ignoring unreadable settings: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save_json(SETTINGS_FILE, self) {
+            log::warn!("Failed to save settings: {}", e);
+        }
+    }
+}
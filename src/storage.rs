@@ -0,0 +1,75 @@
+// storage.rs - JSON files under browser_data
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const DATA_DIR: &str = "browser_data";
+
+pub fn data_path(file: &str) -> PathBuf {
+    PathBuf::from(DATA_DIR).join(file)
+}
+
+/// Reads `browser_data/<file>`; a missing file is `Ok(None)`, a corrupt one an error.
+pub fn load_json<T: DeserializeOwned>(file: &str) -> io::Result<Option<T>> {
+    let content = match fs::read_to_string(data_path(file)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Writes through a temporary file so a crash mid-write never leaves a truncated file.
+pub fn save_json<T: Serialize>(file: &str, value: &T) -> io::Result<()> {
+    let path = data_path(file);
+    let tmp = path.with_extension("tmp");
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::create_dir_all(DATA_DIR)?;
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, path)
+}
+
+/// Reads a JSON Lines file, skipping lines that fail to parse.
+pub fn load_json_lines<T: DeserializeOwned>(file: &str) -> io::Result<Vec<T>> {
+    let content = match fs::read_to_string(data_path(file)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+pub fn append_json_line<T: Serialize>(file: &str, value: &T) -> io::Result<()> {
+    let mut line = serde_json::to_string(value)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    line.push('\n');
+    fs::create_dir_all(DATA_DIR)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_path(file))?
+        .write_all(line.as_bytes())
+}
+
+/// Rewrites a JSON Lines file atomically, e.g. after deleting records.
+pub fn save_json_lines<T: Serialize>(file: &str, values: &[T]) -> io::Result<()> {
+    let path = data_path(file);
+    let tmp = path.with_extension("tmp");
+    let mut out = String::new();
+    for value in values {
+        out.push_str(&serde_json::to_string(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+        out.push('\n');
+    }
+    fs::create_dir_all(DATA_DIR)?;
+    fs::write(&tmp, out)?;
+    fs::rename(tmp, path)
+}
@@ -0,0 +1,208 @@
+// tab_history.rs - Back/forward session history for a single tab
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Serialize, Deserialize};
+
+/// Oldest entries are dropped beyond this to keep long-lived tabs small.
+const MAX_ENTRIES: usize = 50;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub display_url: String,
+    pub title: String,
+    /// Seconds since the Unix epoch when the entry was committed.
+    pub timestamp: u64,
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    /// The plain-http URL this entry was upgraded from by HTTPS-only mode.
+    #[serde(default)]
+    pub upgraded_from: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(url: &str, display_url: &str, title: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            display_url: display_url.to_string(),
+            title: title.to_string(),
+            timestamp: now_secs(),
+            scroll_x: 0.0,
+            scroll_y: 0.0,
+            upgraded_from: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TabHistory {
+    entries: Vec<HistoryEntry>,
+    index: usize,
+}
+
+impl TabHistory {
+    pub fn new(first: HistoryEntry) -> Self {
+        Self {
+            entries: vec![first],
+            index: 0,
+        }
+    }
+
+    /// Commits a new entry after the current one, discarding any forward entries.
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.index + 1);
+        }
+        self.entries.push(entry);
+
+        if self.entries.len() > MAX_ENTRIES {
+            let excess = self.entries.len() - MAX_ENTRIES;
+            self.entries.drain(..excess);
+        }
+        self.index = self.entries.len() - 1;
+    }
+
+    /// Swaps the current entry for `entry`, e.g. when the page redirected,
+    /// so Back skips the URL that was never really shown.
+    pub fn replace(&mut self, entry: HistoryEntry) {
+        match self.entries.get_mut(self.index) {
+            Some(current) => *current = entry,
+            None => self.push(entry),
+        }
+    }
+
+    /// Moves `delta` entries back (negative) or forward (positive).
+    /// Returns `None` and leaves the position untouched if out of range.
+    pub fn go(&mut self, delta: isize) -> Option<&HistoryEntry> {
+        let target = self.index as isize + delta;
+        if delta == 0 || target < 0 || target as usize >= self.entries.len() {
+            return None;
+        }
+        self.index = target as usize;
+        self.entries.get(self.index)
+    }
+
+    pub fn current(&self) -> Option<&HistoryEntry> {
+        self.entries.get(self.index)
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut HistoryEntry> {
+        self.entries.get_mut(self.index)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.index > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.index + 1 < self.entries.len()
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(urls: &[&str]) -> TabHistory {
+        let mut history = TabHistory::new(HistoryEntry::new(urls[0], urls[0], urls[0]));
+        for url in &urls[1..] {
+            history.push(HistoryEntry::new(url, url, url));
+        }
+        history
+    }
+
+    fn urls(history: &TabHistory) -> Vec<&str> {
+        history.entries().iter().map(|e| e.url.as_str()).collect()
+    }
+
+    fn current(history: &TabHistory) -> &str {
+        &history.current().unwrap().url
+    }
+
+    #[test]
+    fn go() {
+        let cases: [(isize, Option<&str>, &str); 7] = [
+            // (delta, returned entry, current entry afterwards), starting at "c"
+            (-1, Some("b"), "b"),
+            (-2, Some("a"), "a"),
+            (-3, None, "c"),
+            (0, None, "c"),
+            (1, None, "c"),
+            (-2, Some("a"), "a"),
+            (isize::MIN, None, "c"),
+        ];
+        for (delta, returned, after) in cases {
+            let mut h = history(&["a", "b", "c"]);
+            assert_eq!(h.go(delta).map(|e| e.url.as_str()), returned, "go({})", delta);
+            assert_eq!(current(&h), after, "go({})", delta);
+        }
+
+        let mut h = history(&["a", "b", "c"]);
+        h.go(-2);
+        assert_eq!(h.go(1).map(|e| e.url.as_str()), Some("b"));
+        assert!(h.can_go_back() && h.can_go_forward());
+        assert_eq!(h.go(1).map(|e| e.url.as_str()), Some("c"));
+        assert!(!h.can_go_forward());
+    }
+
+    #[test]
+    fn push_discards_forward_entries() {
+        let mut h = history(&["a", "b", "c"]);
+        h.go(-2);
+        h.push(HistoryEntry::new("d", "d", "d"));
+        assert_eq!(urls(&h), ["a", "d"]);
+        assert_eq!(h.index(), 1);
+        assert!(!h.can_go_forward());
+    }
+
+    #[test]
+    fn replace_keeps_position() {
+        let mut h = history(&["a", "b", "c"]);
+        h.go(-1);
+        h.replace(HistoryEntry::new("b2", "b2", "b2"));
+        assert_eq!(urls(&h), ["a", "b2", "c"]);
+        assert_eq!(current(&h), "b2");
+        assert!(h.can_go_forward());
+
+        let mut empty = TabHistory::default();
+        empty.replace(HistoryEntry::new("a", "a", "a"));
+        assert_eq!(urls(&empty), ["a"]);
+        assert_eq!(empty.index(), 0);
+    }
+
+    #[test]
+    fn truncates_at_max_entries() {
+        let names: Vec<String> = (0..MAX_ENTRIES + 5).map(|i| i.to_string()).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut h = history(&names);
+        assert_eq!(h.entries().len(), MAX_ENTRIES);
+        assert_eq!(h.entries()[0].url, "5");
+        assert_eq!(current(&h), (MAX_ENTRIES + 4).to_string());
+        assert_eq!(h.index(), MAX_ENTRIES - 1);
+
+        // Going back to the oldest kept entry stops there.
+        assert!(h.go(-(MAX_ENTRIES as isize - 1)).is_some());
+        assert_eq!(current(&h), "5");
+        assert!(h.go(-1).is_none());
+
+        // Pushing from the middle truncates the forward entries first.
+        h.go(10);
+        h.push(HistoryEntry::new("new", "new", "new"));
+        assert_eq!(h.entries().len(), 12);
+        assert_eq!(current(&h), "new");
+    }
+}
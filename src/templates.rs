@@ -0,0 +1,68 @@
+// templates.rs - Renders `{{name}}` placeholders in internal pages with live BrowserCore data
+use std::collections::HashMap;
+use crate::browser_core::BrowserCore;
+use crate::content_index::escape_html;
+use crate::process_memory::{MemoryBreakdown, MemoryUsage};
+
+/// Values available to templated pages, already formatted for display.
+pub struct PageContext {
+    values: HashMap<&'static str, String>,
+}
+
+impl PageContext {
+    pub fn from_core(core: &BrowserCore) -> Self {
+        let stats = core.get_memory_stats();
+        let on_off = |on: bool| if on { "ON" } else { "OFF" }.to_string();
+        let checked = |on: bool| if on { "checked" } else { "" }.to_string();
+        let process_mb = |mb: fn(&MemoryBreakdown) -> usize| {
+            stats.breakdown.as_ref().map_or("--".to_string(), |b| mb(b).to_string())
+        };
+        let usage_mb = |kb: fn(&MemoryUsage) -> Option<u64>| {
+            stats.breakdown.as_ref().and_then(|b| kb(&b.totals)).map_or("--".to_string(), |kb| (kb / 1024).to_string())
+        };
+
+        let values = HashMap::from([
+            ("memory_mb", stats.total_mb.to_string()),
+            ("memory_limit_mb", stats.limit_mb.to_string()),
+            ("memory_percent", (stats.total_mb * 100 / stats.limit_mb.max(1)).to_string()),
+            ("tab_count", stats.tab_count.to_string()),
+            ("ui_mb", process_mb(|b| b.ui_mb)),
+            ("web_mb", process_mb(|b| b.web_mb)),
+            ("network_mb", process_mb(|b| b.network_mb)),
+            ("rss_mb", usage_mb(|u| Some(u.rss_kb))),
+            ("pss_mb", usage_mb(|u| u.pss_kb)),
+            ("uss_mb", usage_mb(|u| u.uss_kb)),
+            ("swap_mb", usage_mb(|u| Some(u.swap_kb))),
+            ("anon_mb", usage_mb(|u| Some(u.anon_kb))),
+            ("file_mb", usage_mb(|u| Some(u.file_kb))),
+            ("booster", on_off(stats.booster_active)),
+            ("booster_checked", checked(stats.booster_active)),
+            ("ultimate_boost", on_off(stats.ultimate_boost_active)),
+            ("ultimate_boost_checked", checked(stats.ultimate_boost_active)),
+            ("flash_status", if core.is_flash_enabled() { core.get_flash_status() } else { "Disabled".to_string() }),
+            ("flash_checked", checked(core.is_flash_enabled())),
+        ]);
+        Self { values }
+    }
+
+    /// Replaces each `{{name}}` with its HTML-escaped value. Unknown names
+    /// render empty so a typo doesn't leak template syntax into the page.
+    pub fn render(&self, template: &str) -> String {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            out.push_str(&rest[..start]);
+            let name = rest[start + 2..start + 2 + len].trim();
+            match self.values.get(name) {
+                Some(value) => out.push_str(&escape_html(value)),
+                None => log::warn!("Unknown template value {{{{{}}}}}", name),
+            }
+            rest = &rest[start + 2 + len + 2..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
@@ -0,0 +1,286 @@
+// url_cleaner.rs - Strips tracking parameters and unwraps redirect links using ClearURLs rules
+use std::collections::BTreeMap;
+use serde::Deserialize;
+use regex::Regex;
+use url::Url;
+use crate::storage;
+
+/// A ClearURLs `data.min.json` placed here replaces the bundled rules.
+pub const RULES_FILE: &str = "clearurls.json";
+const BUNDLED_RULES: &str = include_str!("assets/clearurls.json");
+/// Redirect wrappers can nest (a Google result linking to a Facebook share...).
+const MAX_UNWRAP_HOPS: usize = 5;
+
+/// The ClearURLs rules format: providers keyed by name. `completeProvider`
+/// (blocking whole sites) and `forceRedirection` have no equivalent here.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct RuleSet {
+    providers: BTreeMap<String, ProviderRules>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct ProviderRules {
+    url_pattern: String,
+    rules: Vec<String>,
+    referral_marketing: Vec<String>,
+    raw_rules: Vec<String>,
+    exceptions: Vec<String>,
+    redirections: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+struct Provider {
+    url_pattern: Regex,
+    /// Matched against whole parameter names.
+    params: Vec<Regex>,
+    raw_rules: Vec<Regex>,
+    exceptions: Vec<Regex>,
+    /// Capture group 1 is the percent-encoded destination.
+    redirections: Vec<Regex>,
+}
+
+impl Provider {
+    fn compile(name: &str, rules: ProviderRules) -> Option<Self> {
+        let url_pattern = match Regex::new(&format!("(?i){}", rules.url_pattern)) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                log::warn!("Skipping URL rules for {}: {}", name, e);
+                return None;
+            }
+        };
+        let compile_all = |patterns: &[String], wrap: fn(&str) -> String| -> Vec<Regex> {
+            patterns
+                .iter()
+                .filter_map(|p| match Regex::new(&wrap(p)) {
+                    Ok(re) => Some(re),
+                    Err(e) => {
+                        log::warn!("Skipping URL rule {:?} for {}: {}", p, name, e);
+                        None
+                    }
+                })
+                .collect()
+        };
+        let params: Vec<String> = rules.rules.into_iter().chain(rules.referral_marketing).collect();
+        Some(Self {
+            url_pattern,
+            params: compile_all(&params, |p| format!("(?i)^(?:{})$", p)),
+            raw_rules: compile_all(&rules.raw_rules, |p| format!("(?i){}", p)),
+            exceptions: compile_all(&rules.exceptions, |p| format!("(?i){}", p)),
+            redirections: compile_all(&rules.redirections, |p| format!("(?i){}", p)),
+        })
+    }
+
+    fn applies_to(&self, url: &str) -> bool {
+        self.url_pattern.is_match(url) && !self.exceptions.iter().any(|e| e.is_match(url))
+    }
+
+    fn redirect_target(&self, url: &str) -> Option<Url> {
+        self.redirections.iter().find_map(|re| {
+            let encoded = re.captures(url)?.get(1)?.as_str();
+            let decoded = urlencoding::decode(encoded).ok()?;
+            Url::parse(&decoded).ok().filter(|u| matches!(u.scheme(), "http" | "https"))
+        })
+    }
+
+    fn strip(&self, mut url: Url) -> Url {
+        if !self.raw_rules.is_empty() {
+            let mut raw = url.as_str().to_string();
+            for rule in &self.raw_rules {
+                raw = rule.replace_all(&raw, "").into_owned();
+            }
+            if let Ok(stripped) = Url::parse(&raw) {
+                url = stripped;
+            }
+        }
+        if let Some(query) = url.query() {
+            let kept = self.filter_params(query);
+            url.set_query(kept.as_deref());
+        }
+        // Some sites put parameters in the fragment (`#utm_source=...`).
+        if let Some(fragment) = url.fragment().filter(|f| f.contains('=')) {
+            let kept = self.filter_params(fragment);
+            url.set_fragment(kept.as_deref());
+        }
+        url
+    }
+
+    /// Drops matching `name=value` pairs, leaving the rest byte for byte.
+    fn filter_params(&self, params: &str) -> Option<String> {
+        let kept: Vec<&str> = params
+            .split('&')
+            .filter(|pair| {
+                let name = pair.split('=').next().unwrap_or("");
+                !pair.is_empty() && !self.params.iter().any(|re| re.is_match(name))
+            })
+            .collect();
+        (!kept.is_empty()).then(|| kept.join("&"))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UrlCleaner {
+    providers: Vec<Provider>,
+}
+
+impl Default for UrlCleaner {
+    fn default() -> Self {
+        Self::from_json(BUNDLED_RULES).expect("bundled clearurls.json is valid")
+    }
+}
+
+impl UrlCleaner {
+    /// The user's browser_data/clearurls.json if present, otherwise the bundled rules.
+    pub fn load() -> Self {
+        match storage::load_json::<RuleSet>(RULES_FILE) {
+            Ok(Some(rules)) => return Self::compile(rules),
+            Ok(None) => {}
+            Err(e) => log::warn!("Ignoring unreadable URL rules: {}", e),
+        }
+        Self::default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json).map(Self::compile)
+    }
+
+    fn compile(rules: RuleSet) -> Self {
+        Self {
+            providers: rules
+                .providers
+                .into_iter()
+                .filter_map(|(name, rules)| Provider::compile(&name, rules))
+                .collect(),
+        }
+    }
+
+    /// Unwraps known redirect links and removes tracking parameters.
+    pub fn clean(&self, url: &Url) -> Url {
+        let mut url = url.clone();
+        let mut hops = 0;
+        'unwrap: loop {
+            for provider in &self.providers {
+                if !provider.applies_to(url.as_str()) {
+                    continue;
+                }
+                if hops < MAX_UNWRAP_HOPS {
+                    if let Some(target) = provider.redirect_target(url.as_str()) {
+                        hops += 1;
+                        url = target;
+                        continue 'unwrap;
+                    }
+                }
+                url = provider.strip(url);
+            }
+            return url;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small rule set in the ClearURLs format, so the tests don't move with
+    /// the bundled rules.
+    const RULES: &str = r#"{
+        "providers": {
+            "global": {
+                "urlPattern": ".*",
+                "rules": ["utm(?:_[a-z_]*)?", "fbclid"],
+                "referralMarketing": ["ref"]
+            },
+            "shop": {
+                "urlPattern": "^https?://shop\\.example/",
+                "rules": ["session"],
+                "rawRules": ["/ref=[^/?]*"],
+                "exceptions": ["^https?://shop\\.example/checkout"]
+            },
+            "wrap": {
+                "urlPattern": "^https?://wrap\\.example/",
+                "redirections": ["^https?://wrap\\.example/out\\?u=([^&]*)"]
+            },
+            "broken": {
+                "urlPattern": "(",
+                "rules": ["id"]
+            }
+        }
+    }"#;
+
+    fn cleaner() -> UrlCleaner {
+        UrlCleaner::from_json(RULES).unwrap()
+    }
+
+    fn cleaned(cleaner: &UrlCleaner, url: &str) -> String {
+        cleaner.clean(&Url::parse(url).unwrap()).to_string()
+    }
+
+    /// `url` behind `depth` layers of the wrap provider's redirect links.
+    fn wrapped(url: &str, depth: usize) -> String {
+        (0..depth).fold(url.to_string(), |inner, _| {
+            format!("https://wrap.example/out?u={}", urlencoding::encode(&inner))
+        })
+    }
+
+    #[test]
+    fn strips_params() {
+        let cleaner = cleaner();
+        let cases = [
+            ("https://a.com/?utm_source=x&id=1&utm_medium=y", "https://a.com/?id=1"),
+            ("https://a.com/?UTM_Source=x", "https://a.com/"),
+            ("https://a.com/?fbclid=x#top", "https://a.com/#top"),
+            ("https://a.com/#utm_source=x&section=2", "https://a.com/#section=2"),
+            ("https://a.com/?ref=x&refresh=1", "https://a.com/?refresh=1"),
+            ("https://a.com/?q=a%20b&&utm_campaign", "https://a.com/?q=a%20b"),
+            ("https://a.com/?id=1", "https://a.com/?id=1"),
+            // Provider-specific rules only apply on their site
+            ("https://shop.example/item/ref=sr_1?session=x&color=red", "https://shop.example/item?color=red"),
+            ("https://a.com/?session=x", "https://a.com/?session=x"),
+            // Exceptions skip the provider, but not the global rules
+            ("https://shop.example/checkout?session=x&utm_source=y", "https://shop.example/checkout?session=x"),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(cleaned(&cleaner, url), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn unwraps_redirects() {
+        let cleaner = cleaner();
+        let cases = [
+            (wrapped("https://a.com/page?utm_source=x&id=1", 1), "https://a.com/page?id=1".to_string()),
+            (wrapped("https://a.com/", 2), "https://a.com/".to_string()),
+            (wrapped("https://a.com/", MAX_UNWRAP_HOPS), "https://a.com/".to_string()),
+            // Deeper nesting stops after MAX_UNWRAP_HOPS, leaving the rest wrapped
+            (wrapped("https://a.com/", MAX_UNWRAP_HOPS + 2), wrapped("https://a.com/", 2)),
+            // Only http(s) destinations are followed
+            (wrapped("javascript:alert(1)", 1), wrapped("javascript:alert(1)", 1)),
+            ("https://wrap.example/home".to_string(), "https://wrap.example/home".to_string()),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(cleaned(&cleaner, &url), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn skips_invalid_providers() {
+        assert_eq!(cleaner().providers.len(), 3);
+        assert!(UrlCleaner::from_json("{").is_err());
+    }
+
+    #[test]
+    fn bundled_rules() {
+        let cleaner = UrlCleaner::default();
+        let cases = [
+            ("https://example.com/?utm_source=x&gclid=y&id=1", "https://example.com/?id=1"),
+            (
+                "https://www.google.com/url?q=https%3A%2F%2Fexample.com%2F%3Futm_source%3Dx&sa=D",
+                "https://example.com/",
+            ),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(cleaned(&cleaner, url), expected, "{}", url);
+        }
+    }
+}